@@ -10,19 +10,42 @@
     unused_qualifications
 )]
 // #![allow(dead_code)]
+// failure_derive generates its impls inside anonymous consts
+#![allow(non_local_definitions)]
 
 //! general hex lib
 extern crate ansi_term;
+#[cfg(feature = "clipboard")]
+extern crate arboard;
+#[cfg(feature = "bz2")]
+extern crate bzip2;
 extern crate clap;
 extern crate failure;
+#[cfg(feature = "gzip")]
+extern crate flate2;
+#[cfg(feature = "sections")]
+extern crate goblin;
+#[cfg(feature = "xz")]
+extern crate lzma_rs;
+#[cfg(feature = "image")]
+extern crate png;
+#[cfg(feature = "zstd")]
+extern crate ruzstd;
+extern crate terminal_size;
 
-use clap::ArgMatches;
+use clap::{App, Arg, ArgMatches};
 use failure::Fail;
 use std::{
+    collections::BTreeMap,
     f64,
     fs::{self, File},
-    io::{self, BufReader, BufWriter, Read, Write},
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, SeekFrom, Write},
+    net::TcpStream,
+    path::Path,
     result,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
 };
 
 #[derive(Fail, Debug)]
@@ -30,8 +53,38 @@ pub enum Error {
     #[fail(display = "IO error: {}", _0)]
     Io(#[cause] io::Error),
 
-    // #[fail(display = "Application error: {}", _0)]
-    // Application(String),
+    #[fail(display = "Application error: {}", _0)]
+    Application(String),
+
+    #[fail(display = "Mismatch: {}", _0)]
+    Mismatch(String),
+
+    #[fail(display = "Not found: {}", _0)]
+    NotFound(String),
+}
+
+/// Exit code of a successful run
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code of an application error, e.g. invalid arguments
+pub const EXIT_ERROR: i32 = 1;
+/// Exit code of a failed check: the input differs from what was expected
+pub const EXIT_MISMATCH: i32 = 2;
+/// Exit code of a search without any match
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// Exit code of an input or output error
+pub const EXIT_IO: i32 = 4;
+
+/// Error implementation
+impl Error {
+    /// process exit code for the error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => EXIT_IO,
+            Error::Application(_) => EXIT_ERROR,
+            Error::Mismatch(_) => EXIT_MISMATCH,
+            Error::NotFound(_) => EXIT_NOT_FOUND,
+        }
+    }
 }
 
 impl From<io::Error> for Error {
@@ -73,8 +126,94 @@ pub enum Format {
     Unknown,
 }
 
+/// Offset column format
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OffsetFormat {
+    /// hexadecimal offsets
+    Hex,
+    /// decimal offsets
+    Decimal,
+    /// octal offsets
+    Octal,
+}
+
+/// Byte class counts of an input
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ClassCounts {
+    /// printable ascii, space excluded
+    pub printable: u64,
+    /// space, tab, line feed, vertical tab, form feed and carriage return
+    pub whitespace: u64,
+    /// other ascii control bytes, delete included
+    pub control: u64,
+    /// bytes with the high bit set
+    pub high: u64,
+    /// zero bytes
+    pub null: u64,
+}
+
+/// ClassCounts implementation
+impl ClassCounts {
+    /// Name of the most frequent class, the first listed on ties.
+    pub fn dominant(&self) -> &'static str {
+        let classes = [
+            ("printable", self.printable),
+            ("whitespace", self.whitespace),
+            ("control", self.control),
+            ("high", self.high),
+            ("null", self.null),
+        ];
+        classes
+            .iter()
+            .fold(
+                classes[0],
+                |best, class| if class.1 > best.1 { *class } else { best },
+            )
+            .0
+    }
+}
+
+/// Summary of one byte position across fixed-size records
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColumnSummary {
+    /// position within the record
+    pub position: u64,
+    /// crc32 of the bytes at this position, record after record
+    pub hash: u32,
+    /// number of distinct values at this position
+    pub distinct: usize,
+    /// byte at this position in the first record
+    pub first: u8,
+}
+
+/// Compression of an input
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Compression {
+    /// gzip, `.gz`
+    Gzip,
+    /// zstandard, `.zst`
+    Zstd,
+    /// bzip2, `.bz2`
+    Bzip2,
+    /// xz, `.xz`
+    Xz,
+}
+
+/// Compression implementation
+impl Compression {
+    /// whether the decoder of this compression is built in
+    pub fn supported(self) -> bool {
+        match self {
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+            Compression::Bzip2 => cfg!(feature = "bz2"),
+            Compression::Xz => cfg!(feature = "xz"),
+        }
+    }
+}
+
 /// Line structure for hex output
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Line {
     /// offset
     pub offset: u64,
@@ -84,6 +223,8 @@ pub struct Line {
     pub ascii: Vec<char>,
     /// total bytes in Line
     pub bytes: u64,
+    /// empty columns before the first byte, to keep an aligned grid
+    pub lead: u64,
 }
 /// Line implementation
 impl Line {
@@ -94,12 +235,13 @@ impl Line {
             hex_body: Vec::new(),
             ascii: Vec::new(),
             bytes: 0x0,
+            lead: 0x0,
         }
     }
 }
 
 /// Page structure
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Page {
     /// page offset
     pub offset: u64,
@@ -119,6 +261,203 @@ impl Page {
             bytes: 0x0,
         }
     }
+
+    /// Bytes of all lines, in order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.body
+            .iter()
+            .flat_map(|line| line.hex_body.iter().cloned())
+            .collect()
+    }
+}
+
+/// Byte order of multi-byte values
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Endian {
+    /// most significant byte first
+    Big,
+    /// least significant byte first
+    Little,
+}
+
+/// Code page used to render the ascii gutter
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Codepage {
+    /// IBM PC / DOS, high bytes are box-drawing and accented glyphs
+    Cp437,
+    /// ISO-8859-1
+    Latin1,
+    /// Windows Western European
+    Cp1252,
+}
+
+/// Transform of the gutter text shown in a side column
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Decoding {
+    /// letters rotated by 13
+    Rot13,
+    /// base64, decoded per whole group of 4 characters
+    Base64,
+    /// percent-encoding, `+` as a space
+    Url,
+}
+
+/// Type-length-value record
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tlv {
+    /// record offset
+    pub offset: u64,
+    /// record type, `None` if the header is truncated
+    pub tag: Option<u64>,
+    /// value length, `None` if the header is truncated
+    pub length: Option<u64>,
+    /// bytes missing when the value overruns the input
+    pub overrun: u64,
+}
+
+/// Struct field type
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FieldKind {
+    /// unsigned integer of the given width in bytes
+    Unsigned(usize),
+    /// signed integer of the given width in bytes
+    Signed(usize),
+    /// string of the given length in bytes
+    Str(usize),
+}
+
+/// Struct layout field
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// field name
+    pub name: String,
+    /// field type
+    pub kind: FieldKind,
+}
+
+/// Dump options, resolved from the command line
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// octet format
+    pub format: Format,
+    /// offset column format
+    pub offset_format: OffsetFormat,
+    /// colorize output
+    pub colorize: bool,
+    /// column width
+    pub column_width: u64,
+    /// record size in bytes, lines never straddle a record boundary
+    pub record_size: Option<u64>,
+    /// record header template, `{n}` is the record index, `{offset}` its start
+    pub record_label: Option<String>,
+    /// decode utf-8 sequences in the ascii gutter
+    pub utf8_gutter: bool,
+    /// minimap bucket averages, printed one per line next to the gutter
+    pub minimap: Vec<u8>,
+    /// characters opening and closing the ascii gutter
+    pub gutter_delim: Option<(char, char)>,
+    /// sorted instruction end offsets, a separator is drawn after each
+    pub insn_boundaries: Vec<u64>,
+    /// label lines with the offset of their last byte
+    pub offset_last: bool,
+    /// notes printed before the line holding their offset
+    pub annotations: BTreeMap<u64, Vec<String>>,
+    /// tighter layout: no radix prefixes, bare offsets
+    pub compact: bool,
+    /// prefix lines with a 1-based line counter
+    pub line_numbers: bool,
+    /// code page of the ascii gutter, `None` keeps plain ascii
+    pub codepage: Option<Codepage>,
+    /// side column showing the gutter text decoded
+    pub decode_column: Option<Decoding>,
+    /// earlier content of the input, lines differing from it get a change bar
+    pub baseline: Option<Vec<u8>>,
+    /// alternate the background of every other line when colorizing
+    pub zebra: bool,
+    /// sorted, disjoint byte ranges `[start, end)` highlighted in the dump
+    pub marks: Vec<(u64, u64)>,
+    /// highlight color of marked bytes
+    pub accent: ansi_term::Color,
+    /// never end a line with whitespace
+    pub no_trailing_space: bool,
+    /// fit the column width to the terminal width
+    pub auto_columns: bool,
+    /// quote printable runs in the gutter, escaping the other bytes
+    pub quote_strings: bool,
+    /// address step per byte in the offset column
+    pub addr_stride: u64,
+    /// show 64-bit little-endian pointers in the gutter
+    pub pointers: bool,
+    /// sorted record starts, offsets are printed relative to their record
+    pub record_starts: Vec<u64>,
+    /// backslash-escape gutter delimiters found in the gutter
+    pub escape_gutter: bool,
+    /// color bytes by alternating groups of this many columns, not by value
+    pub alt_group: Option<u64>,
+    /// list the set bits of every byte after the gutter
+    pub bitmask: bool,
+    /// leave out the byte count closing the dump
+    pub no_summary: bool,
+    /// input length, to prefix every line with how far through it it starts
+    pub percent_of: Option<u64>,
+    /// number of identical lines left out at the end of the page
+    pub trimmed_lines: usize,
+    /// show the bytes of every group of this many reversed, gutter unchanged
+    pub swap_words: Option<u64>,
+    /// crc32 of every line with how it compares to the line before, by row
+    pub crc_deltas: Vec<(u32, char)>,
+    /// link target template of offsets, `{offset}` is the line offset
+    pub hyperlink: Option<String>,
+}
+
+/// Options implementation
+impl Options {
+    /// Options constructor
+    pub fn new() -> Options {
+        Options {
+            format: Format::LowerHex,
+            offset_format: OffsetFormat::Hex,
+            colorize: true,
+            column_width: 10,
+            record_size: None,
+            record_label: None,
+            utf8_gutter: false,
+            minimap: Vec::new(),
+            gutter_delim: None,
+            insn_boundaries: Vec::new(),
+            offset_last: false,
+            annotations: BTreeMap::new(),
+            compact: false,
+            line_numbers: false,
+            codepage: None,
+            baseline: None,
+            zebra: false,
+            marks: Vec::new(),
+            accent: ansi_term::Color::Red,
+            no_trailing_space: false,
+            auto_columns: false,
+            quote_strings: false,
+            addr_stride: 1,
+            pointers: false,
+            record_starts: Vec::new(),
+            escape_gutter: false,
+            alt_group: None,
+            bitmask: false,
+            no_summary: false,
+            percent_of: None,
+            trimmed_lines: 0,
+            swap_words: None,
+            crc_deltas: Vec::new(),
+            decode_column: None,
+            hyperlink: None,
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options::new()
+    }
 }
 
 /// offset column
@@ -130,6 +469,151 @@ pub fn offset(b: u64) -> String {
     format!("{:#08x}", b)
 }
 
+/// Offset column in the given format.
+///
+/// # Arguments
+///
+/// * `b` - offset value.
+/// * `format` - offset format.
+/// * `prefix` - radix prefix and a width of 8, bare width of 6 otherwise.
+pub fn format_offset(b: u64, format: OffsetFormat, prefix: bool) -> String {
+    match (format, prefix) {
+        (OffsetFormat::Hex, true) => offset(b),
+        (OffsetFormat::Hex, false) => format!("{:06x}", b),
+        (OffsetFormat::Decimal, true) => format!("{:08}", b),
+        (OffsetFormat::Decimal, false) => format!("{:06}", b),
+        (OffsetFormat::Octal, true) => format!("{:#08o}", b),
+        (OffsetFormat::Octal, false) => format!("{:06o}", b),
+    }
+}
+
+/// Parse an offset format name.
+///
+/// # Arguments
+///
+/// * `s` - `x`, `d` or `o`.
+pub fn parse_offset_format(s: &str) -> Option<OffsetFormat> {
+    match s {
+        "x" => Some(OffsetFormat::Hex),
+        "d" => Some(OffsetFormat::Decimal),
+        "o" => Some(OffsetFormat::Octal),
+        _ => None,
+    }
+}
+
+/// Parse a color mode: `0`/`never`, `1`/`always`, or `auto` to colorize
+/// only a terminal.
+///
+/// # Arguments
+///
+/// * `s` - The mode.
+/// * `is_terminal` - Whether the output is a terminal.
+pub fn parse_color_mode(s: &str, is_terminal: bool) -> Option<bool> {
+    match s {
+        "0" | "never" => Some(false),
+        "1" | "always" => Some(true),
+        "auto" => Some(is_terminal),
+        _ => None,
+    }
+}
+
+/// Parse a color name of the basic terminal palette.
+///
+/// # Arguments
+///
+/// * `s` - black, red, green, yellow, blue, purple (or magenta), cyan or white.
+pub fn parse_color_name(s: &str) -> Option<ansi_term::Color> {
+    match s {
+        "black" => Some(ansi_term::Color::Black),
+        "red" => Some(ansi_term::Color::Red),
+        "green" => Some(ansi_term::Color::Green),
+        "yellow" => Some(ansi_term::Color::Yellow),
+        "blue" => Some(ansi_term::Color::Blue),
+        "purple" | "magenta" => Some(ansi_term::Color::Purple),
+        "cyan" => Some(ansi_term::Color::Cyan),
+        "white" => Some(ansi_term::Color::White),
+        _ => None,
+    }
+}
+
+/// Runs of at least `min_run` identical bytes, as `(start, byte, length)`.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to scan.
+/// * `base` - Offset of the first byte.
+/// * `min_run` - Shortest run reported.
+pub fn byte_runs(bytes: &[u8], base: u64, min_run: u64) -> Vec<(u64, u8, u64)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        let len = bytes[start..]
+            .iter()
+            .take_while(|b| **b == bytes[start])
+            .count();
+        if len as u64 >= min_run {
+            runs.push((base + start as u64, bytes[start], len as u64));
+        }
+        start += len;
+    }
+    runs
+}
+
+/// Byte ranges of every occurrence of `pattern` in `bytes`, overlapping
+/// occurrences merged.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to search.
+/// * `base` - Offset of the first byte.
+/// * `pattern` - Bytes to find.
+pub fn find_marks(bytes: &[u8], base: u64, pattern: &[u8]) -> Vec<(u64, u64)> {
+    let mut marks: Vec<(u64, u64)> = Vec::new();
+    if pattern.is_empty() {
+        return marks;
+    }
+    for (i, window) in bytes.windows(pattern.len()).enumerate() {
+        if window != pattern {
+            continue;
+        }
+        let (start, end) = (base + i as u64, base + (i + pattern.len()) as u64);
+        match marks.last_mut() {
+            Some(last) if last.1 >= start => last.1 = end,
+            _ => marks.push((start, end)),
+        }
+    }
+    marks
+}
+
+/// Whether an offset is within one of the sorted, disjoint marks.
+///
+/// # Arguments
+///
+/// * `marks` - Byte ranges `[start, end)`.
+/// * `at` - The offset.
+pub fn is_marked(marks: &[(u64, u64)], at: u64) -> bool {
+    let i = marks.partition_point(|(start, _)| *start <= at);
+    i > 0 && at < marks[i - 1].1
+}
+
+/// Parse an octet format letter.
+///
+/// # Arguments
+///
+/// * `s` - o, x, X, p, b, e or E.
+pub fn parse_format(s: &str) -> Format {
+    match s {
+        "o" => Format::Octal,
+        "x" => Format::LowerHex,
+        "X" => Format::UpperHex,
+        "p" => Format::Pointer,
+        "b" => Format::Binary,
+        "e" => Format::LowerExp,
+        "E" => Format::UpperExp,
+        _ => Format::Unknown,
+    }
+}
+
 /// print offset to std out
 pub fn print_offset(b: u64) {
     print!("{}: ", offset(b));
@@ -142,12 +626,29 @@ pub fn hex_octal(b: u8) -> String {
 
 /// hex lower hex, takes u8
 pub fn hex_lower_hex(b: u8) -> String {
-    format!("{:#04x}", b)
+    hex_octet(b, false, true)
 }
 
 /// hex upper hex, takes u8
 pub fn hex_upper_hex(b: u8) -> String {
-    format!("{:#04X}", b)
+    hex_octet(b, true, true)
+}
+
+/// Hex octet of `b` in the requested case, `0x` prefixed or bare. All hex
+/// octet renderings go through here.
+///
+/// # Arguments
+///
+/// * `b` - Byte to render.
+/// * `upper` - Render the digits upper case.
+/// * `prefix` - Prefix with `0x`.
+pub fn hex_octet(b: u8, upper: bool, prefix: bool) -> String {
+    match (upper, prefix) {
+        (false, true) => format!("{:#04x}", b),
+        (false, false) => format!("{:02x}", b),
+        (true, true) => format!("{:#04X}", b),
+        (true, false) => format!("{:02X}", b),
+    }
 }
 
 /// hex binary, takes u8
@@ -157,55 +658,74 @@ pub fn hex_binary(b: u8) -> String {
 
 /// print byte to std out
 pub fn print_byte<T: Write>(b: u8, format: Format, colorize: bool, w: &mut T) -> Result<()> {
+    print_byte_sep(b, format, colorize, ' ', w)
+}
+
+/// print byte followed by a separator character
+pub fn print_byte_sep<T: Write>(
+    b: u8,
+    format: Format,
+    colorize: bool,
+    sep: char,
+    w: &mut T,
+) -> Result<()> {
+    let text = format_byte(b, format, true);
+    let write_result = if colorize {
+        // note, for color testing: for (( i = 0; i < 256; i++ )); do echo "$(tput setaf $i)This is ($i) $(tput sgr0)"; done
+        write!(w, "{}{}", byte_style(b).paint(text), sep)
+    } else {
+        write!(w, "{}{}", text, sep)
+    };
+
+    write_result.map_err(Error::Io)
+}
+
+/// byte color, the 256 color palette entry of the byte value
+pub fn byte_style(b: u8) -> ansi_term::Style {
     let mut color: u8 = b;
     if color < 1 {
         color = 0x16;
     }
+    ansi_term::Style::new().fg(ansi_term::Color::Fixed(color))
+}
 
-    let write_result = if colorize {
-        // note, for color testing: for (( i = 0; i < 256; i++ )); do echo "$(tput setaf $i)This is ($i) $(tput sgr0)"; done
-        match format {
-            Format::Octal => write!(
-                w,
-                "{} ",
-                ansi_term::Style::new()
-                    .fg(ansi_term::Color::Fixed(color))
-                    .paint(hex_octal(b))
-            ),
-            Format::LowerHex => write!(
-                w,
-                "{} ",
-                ansi_term::Style::new()
-                    .fg(ansi_term::Color::Fixed(color))
-                    .paint(hex_lower_hex(b))
-            ),
-            Format::UpperHex => write!(
-                w,
-                "{} ",
-                ansi_term::Style::new()
-                    .fg(ansi_term::Color::Fixed(color))
-                    .paint(hex_upper_hex(b))
-            ),
-            Format::Binary => write!(
-                w,
-                "{} ",
-                ansi_term::Style::new()
-                    .fg(ansi_term::Color::Fixed(color))
-                    .paint(hex_binary(b))
-            ),
-            _ => write!(w, "{}", "unk_fmt "),
-        }
+/// Style of the bytes in the `column`th column with `--alt-group-color`:
+/// light and dark tints alternating every `group_size` columns.
+///
+/// # Arguments
+///
+/// * `column` - Column of the byte within its line.
+/// * `group_size` - Columns per group.
+pub fn group_style(column: u64, group_size: u64) -> ansi_term::Style {
+    let tint = if (column / group_size.max(1)).is_multiple_of(2) {
+        255
     } else {
-        match format {
-            Format::Octal => write!(w, "{} ", hex_octal(b)),
-            Format::LowerHex => write!(w, "{} ", hex_lower_hex(b)),
-            Format::UpperHex => write!(w, "{} ", hex_upper_hex(b)),
-            Format::Binary => write!(w, "{} ", hex_binary(b)),
-            _ => write!(w, "{}", "unk_fmt "),
-        }
+        245
     };
+    ansi_term::Style::new().fg(ansi_term::Color::Fixed(tint))
+}
 
-    write_result.map_err(Error::Io)
+/// Style of array elements changed from the baseline.
+pub fn change_style() -> ansi_term::Style {
+    ansi_term::Color::Red.bold()
+}
+
+/// Background of every other line with `--zebra`.
+pub const ZEBRA_BACKGROUND: ansi_term::Color = ansi_term::Color::Fixed(236);
+
+/// byte in the given format, with or without its radix prefix
+pub fn format_byte(b: u8, format: Format, prefix: bool) -> String {
+    match (format, prefix) {
+        (Format::Octal, true) => hex_octal(b),
+        (Format::Octal, false) => format!("{:03o}", b),
+        (Format::LowerHex, true) => hex_lower_hex(b),
+        (Format::LowerHex, false) => hex_octet(b, false, false),
+        (Format::UpperHex, true) => hex_upper_hex(b),
+        (Format::UpperHex, false) => hex_octet(b, true, false),
+        (Format::Binary, true) => hex_binary(b),
+        (Format::Binary, false) => format!("{:08b}", b),
+        _ => "unk_fmt".to_string(),
+    }
 }
 
 /// Function wave out.
@@ -222,236 +742,8795 @@ pub fn func_out(len: u64, places: usize) {
         print!("{}", formatted_number);
         print!(",");
         if (y % 10) == 9 {
-            println!("");
+            println!();
         }
     }
-    println!("");
+    println!();
 }
 
-/// In most hex editor applications, the data of the computer file is
-/// represented as hexadecimal values grouped in 4 groups of 4 bytes
-/// (or two groups of 8 bytes), followed by one group of 16 printable ASCII
-/// characters which correspond to each pair of hex values (each byte).
-/// Non-printable ASCII characters (e.g., Bell) and characters that would take
-/// more than one character space (e.g., tab) are typically represented by a
-/// dot (".") in the following ASCII field.
+/// printable ascii byte
+pub fn is_printable(b: u8) -> bool {
+    b > 31 && b < 127
+}
+
+/// Byte order mark at the start of the buffer, if any.
 ///
 /// # Arguments
 ///
-/// * `matches` - Argument matches from command line.
-pub fn run(matches: ArgMatches) -> Result<()> {
-    let stdout = io::stdout();
-    let stdout = stdout.lock();
-    let mut stdout = BufWriter::new(stdout);
+/// * `buf` - Buffer to inspect.
+pub fn detect_bom(buf: &[u8]) -> Option<&'static str> {
+    if buf.starts_with(&[0xef, 0xbb, 0xbf]) {
+        Some("UTF-8")
+    } else if buf.starts_with(&[0xff, 0xfe]) {
+        Some("UTF-16LE")
+    } else if buf.starts_with(&[0xfe, 0xff]) {
+        Some("UTF-16BE")
+    } else {
+        None
+    }
+}
 
-    let mut column_width: u64 = 10;
-    if let Some(len) = matches.value_of("func") {
-        let mut p: usize = 4;
-        if let Some(places) = matches.value_of("places") {
-            p = places.parse::<usize>().unwrap();
-        }
-        func_out(len.parse::<u64>().unwrap(), p);
-    } else if let Some(file) = matches.value_of("INPUTFILE") {
-        let f = File::open(file).unwrap();
-        let mut buf_len = fs::metadata(file)?.len();
-        let mut buf = BufReader::new(f);
-        let mut format_out = Format::LowerHex;
-        let mut colorize = true;
+/// Length of the utf-8 sequence starting at `buf[0]`, if it is a complete,
+/// valid multi-byte sequence.
+fn utf8_sequence_len(buf: &[u8]) -> Option<usize> {
+    let len = match buf.first() {
+        Some(0xc2..=0xdf) => 2,
+        Some(0xe0..=0xef) => 3,
+        Some(0xf0..=0xf4) => 4,
+        _ => return None,
+    };
+    if buf.len() >= len && std::str::from_utf8(&buf[..len]).is_ok() {
+        Some(len)
+    } else {
+        None
+    }
+}
 
-        if let Some(columns) = matches.value_of("cols") {
-            column_width = columns.parse::<u64>().unwrap(); //turbofish
+/// Ratio of text bytes in the sample: printable ascii, whitespace and
+/// valid utf-8 sequences.
+///
+/// # Arguments
+///
+/// * `sample` - Bytes to inspect.
+pub fn printable_ratio(sample: &[u8]) -> f64 {
+    if sample.is_empty() {
+        return 0.0;
+    }
+    let mut text: usize = 0;
+    let mut i: usize = 0;
+    while i < sample.len() {
+        let b = sample[i];
+        if is_printable(b) || b == b'\t' || b == b'\n' || b == b'\r' {
+            text += 1;
+            i += 1;
+        } else if let Some(len) = utf8_sequence_len(&sample[i..]) {
+            text += len;
+            i += len;
+        } else {
+            i += 1;
         }
+    }
+    text as f64 / sample.len() as f64
+}
 
-        if let Some(length) = matches.value_of("len") {
-            buf_len = length.parse::<u64>().unwrap();
-        }
+/// Text heuristic for `--auto`: a byte order mark, or mostly text bytes.
+///
+/// # Arguments
+///
+/// * `sample` - First bytes of the input, typically 1KB.
+pub fn detect_text(sample: &[u8]) -> bool {
+    detect_bom(sample).is_some() || printable_ratio(sample) >= 0.9
+}
 
-        if let Some(format) = matches.value_of("format") {
-            // o, x, X, p, b, e, E
-            match format {
-                "o" => format_out = Format::Octal,
-                "x" => format_out = Format::LowerHex,
-                "X" => format_out = Format::UpperHex,
-                "p" => format_out = Format::Pointer,
-                "b" => format_out = Format::Binary,
-                "e" => format_out = Format::LowerExp,
-                "E" => format_out = Format::UpperExp,
-                _ => format_out = Format::Unknown,
-            }
-        }
+/// Ascii gutter: printable ascii as is, anything else as a dot.
+///
+/// # Arguments
+///
+/// * `bytes` - Line bytes.
+pub fn ascii_gutter(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| if is_printable(*b) { *b as char } else { '.' })
+        .collect()
+}
 
-        if let Some(color) = matches.value_of("color") {
-            let color_v = color.parse::<u8>().unwrap();
-            if color_v == 1 {
-                colorize = true;
-            } else {
-                colorize = false;
+/// Quoted gutter: runs of printable ascii in double quotes, anything else
+/// as an escape between them.
+///
+/// # Arguments
+///
+/// * `bytes` - Line bytes.
+pub fn quoted_gutter(bytes: &[u8]) -> String {
+    let mut gutter = String::new();
+    let mut quoted = false;
+    for b in bytes {
+        if is_printable(*b) != quoted {
+            gutter.push('"');
+            quoted = !quoted;
+        }
+        match *b {
+            b'"' | b'\\' => {
+                gutter.push('\\');
+                gutter.push(*b as char);
             }
+            _ if quoted => gutter.push(*b as char),
+            0 => gutter.push_str("\\0"),
+            b'\t' => gutter.push_str("\\t"),
+            b'\n' => gutter.push_str("\\n"),
+            b'\r' => gutter.push_str("\\r"),
+            _ => gutter.push_str(&format!("\\x{:02x}", b)),
         }
+    }
+    if quoted {
+        gutter.push('"');
+    }
+    gutter
+}
+
+/// Pointer gutter: every 8-byte group of the line as a little-endian 64-bit
+/// pointer, the missing high bytes of a trailing partial group as `??`.
+///
+/// # Arguments
+///
+/// * `bytes` - Line bytes.
+pub fn pointer_gutter(bytes: &[u8]) -> String {
+    bytes
+        .chunks(8)
+        .map(|group| {
+            let digits: String = (0..8)
+                .rev()
+                .map(|i| match group.get(i) {
+                    Some(b) => format!("{:02x}", b),
+                    None => "??".to_string(),
+                })
+                .collect();
+            format!("0x{}", digits)
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
 
-        match matches.occurrences_of("v") {
-            0 => write!(&mut stdout, "")?,
-            1 => write!(&mut stdout, "verbose 1")?,
-            2 => write!(&mut stdout, "verbose 2")?,
-            3 | _ => write!(&mut stdout, "verbose max")?,
+/// Backslash-escape the gutter delimiters and backslashes within a gutter,
+/// keeping its boundaries unambiguous.
+///
+/// # Arguments
+///
+/// * `gutter` - Gutter text.
+/// * `delim` - Gutter delimiters.
+pub fn escape_gutter(gutter: &str, delim: Option<(char, char)>) -> String {
+    let mut escaped = String::with_capacity(gutter.len());
+    for c in gutter.chars() {
+        if c == '\\' || delim.is_some_and(|(open, close)| c == open || c == close) {
+            escaped.push('\\');
         }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Positions of the bits set in a byte, most significant first, as `[7 4 0]`.
+///
+/// # Arguments
+///
+/// * `b` - The byte.
+pub fn set_bits(b: u8) -> String {
+    let bits: Vec<String> = (0..8)
+        .rev()
+        .filter(|bit| b & (1 << bit) != 0)
+        .map(|bit| bit.to_string())
+        .collect();
+    format!("[{}]", bits.join(" "))
+}
 
-        // array output mode is mutually exclusive
-        if let Some(array) = matches.value_of("array") {
-            let mut array_format = array;
-            let mut page = buf_to_array(&mut buf, buf_len, column_width).unwrap();
-            match array_format {
-                "r" => writeln!(&mut stdout, "let ARRAY: [u8; {}] = [", page.bytes)?,
-                "c" => writeln!(&mut stdout, "unsigned char ARRAY[{}] = {{", page.bytes)?,
-                "g" => writeln!(&mut stdout, "a := [{}]byte{{", page.bytes)?,
-                _ => writeln!(&mut stdout, "unknown array format")?,
+/// Utf-8 gutter: complete multi-byte sequences within the line are decoded,
+/// everything else is rendered as in the ascii gutter.
+///
+/// # Arguments
+///
+/// * `bytes` - Line bytes.
+pub fn utf8_gutter(bytes: &[u8]) -> String {
+    let mut gutter = String::new();
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        if let Some(len) = utf8_sequence_len(&bytes[i..]) {
+            if let Ok(s) = std::str::from_utf8(&bytes[i..i + len]) {
+                gutter.push_str(s);
             }
+            i += len;
+        } else {
+            gutter.push(if is_printable(bytes[i]) {
+                bytes[i] as char
+            } else {
+                '.'
+            });
+            i += 1;
+        }
+    }
+    gutter
+}
+
+/// Cp437 glyphs of the bytes 0x80 to 0xff.
+const CP437_HIGH: &str = "\
+    ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// Cp1252 glyphs of the bytes 0x80 to 0x9f, dots are undefined bytes.
+const CP1252_C1: &str = "€.‚ƒ„…†‡ˆ‰Š‹Œ.Ž..‘’“”•–—˜™š›œ.žŸ";
+
+/// Parse a code page name.
+///
+/// # Arguments
+///
+/// * `s` - `cp437`, `latin1` or `cp1252`.
+pub fn parse_codepage(s: &str) -> Option<Codepage> {
+    match s {
+        "cp437" => Some(Codepage::Cp437),
+        "latin1" => Some(Codepage::Latin1),
+        "cp1252" => Some(Codepage::Cp1252),
+        _ => None,
+    }
+}
+
+/// Parse a side column decoding name.
+///
+/// # Arguments
+///
+/// * `s` - `rot13`, `base64` or `url`.
+pub fn parse_decoding(s: &str) -> Option<Decoding> {
+    match s {
+        "rot13" => Some(Decoding::Rot13),
+        "base64" => Some(Decoding::Base64),
+        "url" => Some(Decoding::Url),
+        _ => None,
+    }
+}
 
-            let mut i: u64 = 0x0;
-            for line in page.body.iter() {
-                write!(&mut stdout, "    ");
-                for hex in line.hex_body.iter() {
-                    i += 1;
-                    if i == buf_len && array_format != "g" {
-                        write!(&mut stdout, "{}", hex_lower_hex(*hex));
-                    } else {
-                        write!(&mut stdout,"{}, ", hex_lower_hex(*hex));
+/// Gutter text decoded for the side column. Decoded bytes that are not
+/// printable are dots, base64 groups that do not decode are `?`.
+///
+/// # Arguments
+///
+/// * `text` - Gutter text.
+/// * `decoding` - Transform to apply.
+pub fn decode_gutter(text: &str, decoding: Decoding) -> String {
+    match decoding {
+        Decoding::Rot13 => text
+            .chars()
+            .map(|c| match c {
+                'a'..='z' => ((c as u8 - b'a' + 13) % 26 + b'a') as char,
+                'A'..='Z' => ((c as u8 - b'A' + 13) % 26 + b'A') as char,
+                _ => c,
+            })
+            .collect(),
+        Decoding::Base64 => {
+            let sextet = |c: u8| match c {
+                b'A'..=b'Z' => Some(c - b'A'),
+                b'a'..=b'z' => Some(c - b'a' + 26),
+                b'0'..=b'9' => Some(c - b'0' + 52),
+                b'+' => Some(62),
+                b'/' => Some(63),
+                _ => None,
+            };
+            let mut decoded = String::new();
+            for group in text.as_bytes().chunks_exact(4) {
+                let data: Vec<u8> = group.iter().take_while(|c| **c != b'=').cloned().collect();
+                let sextets: Option<Vec<u8>> = data.iter().map(|c| sextet(*c)).collect();
+                match sextets.filter(|sextets| sextets.len() >= 2) {
+                    Some(sextets) => {
+                        let bits = sextets
+                            .iter()
+                            .fold(0u32, |bits, sextet| bits << 6 | u32::from(*sextet))
+                            << (6 * (4 - sextets.len()));
+                        for i in 0..sextets.len() - 1 {
+                            decoded.push(printable_or_dot((bits >> (16 - 8 * i)) as u8));
+                        }
                     }
+                    None => decoded.push('?'),
                 }
-                writeln!(&mut stdout, "");
             }
-            match array_format {
-                "r" => writeln!(&mut stdout, "{}", "];")?,
-                "c" => writeln!(&mut stdout, "{}", "};")?,
-                "g" => writeln!(&mut stdout, "{}", "}")?,
-                _ => writeln!(&mut stdout, "unknown array format")?,
-            }
-        } else {
-            // Transforms this Read instance to an Iterator over its bytes.
-            // The returned type implements Iterator where the Item is
-            // Result<u8, R::Err>. The yielded item is Ok if a byte was
-            // successfully read and Err otherwise for I/O errors. EOF is mapped
-            // to returning None from this iterator.
-            // (https://doc.rust-lang.org/1.16.0/std/io/trait.Read.html#method.bytes)
-            let mut ascii_line: Line = Line::new();
-            let mut offset_counter: u64 = 0x0;
-            let mut byte_column: u64 = 0x0;
-            let mut page = buf_to_array(&mut buf, buf_len, column_width).unwrap();
-
-            for line in page.body.iter() {
-                print_offset(offset_counter);
-
-                for hex in line.hex_body.iter() {
-                    offset_counter += 1;
-                    byte_column += 1;
-                    print_byte(*hex, format_out, colorize, &mut stdout)?;
-
-                    if *hex > 31 && *hex < 127 {
-                        ascii_line.ascii.push(*hex as char);
-                    } else {
-                        ascii_line.ascii.push('.');
+            decoded
+        }
+        Decoding::Url => {
+            let bytes = text.as_bytes();
+            let mut decoded = String::new();
+            let mut i = 0;
+            while i < bytes.len() {
+                let escaped = match bytes[i] {
+                    b'%' => text
+                        .get(i + 1..i + 3)
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None,
+                };
+                match (escaped, bytes[i]) {
+                    (Some(b), _) => {
+                        decoded.push(printable_or_dot(b));
+                        i += 3;
+                    }
+                    (None, b'+') => {
+                        decoded.push(' ');
+                        i += 1;
+                    }
+                    (None, b) => {
+                        decoded.push(printable_or_dot(b));
+                        i += 1;
                     }
                 }
-
-                if byte_column < column_width {
-                    write!(&mut stdout, "{:<1$}", "", 5 * (column_width - byte_column) as usize);
-                }
-
-                byte_column = 0x0;
-                let ascii_string: String = ascii_line.ascii.iter().cloned().collect();
-                ascii_line = Line::new();
-                write!(&mut stdout, "{}", ascii_string); // print ascii string
-                writeln!(&mut stdout, "");
-            }
-            if true {
-                writeln!(&mut stdout, "   bytes: {}", page.bytes);
             }
+            decoded
         }
     }
-    Ok(())
 }
 
-/// Buffer to array.
+/// Printable ascii character of a byte, a dot otherwise.
+fn printable_or_dot(b: u8) -> char {
+    if is_printable(b) {
+        b as char
+    } else {
+        '.'
+    }
+}
+
+/// Character of a byte in a code page, control bytes are dots.
 ///
-/// (https://rustbyexample.com/primitives/array.html)
-/// (https://stackoverflow.com/questions/39464237/whats-the-idiomatic-way-reference-bufreader-bufwriter-when-passing-between-funct)
-/// (https://stackoverflow.com/questions/39935158/bufreader-move-after-for-loop-with-bufreader-lines)
+/// # Arguments
+///
+/// * `b` - The byte.
+/// * `codepage` - The code page.
+pub fn codepage_char(b: u8, codepage: Codepage) -> char {
+    if is_printable(b) {
+        return b as char;
+    }
+    if b < 0x80 {
+        return '.';
+    }
+    let high = (b - 0x80) as usize;
+    match codepage {
+        Codepage::Cp437 => CP437_HIGH.chars().nth(high).unwrap_or('.'),
+        Codepage::Latin1 | Codepage::Cp1252 if b >= 0xa0 => b as char,
+        Codepage::Latin1 => '.',
+        Codepage::Cp1252 => CP1252_C1.chars().nth(high).unwrap_or('.'),
+    }
+}
+
+/// Code page gutter: every byte rendered through the code page.
 ///
 /// # Arguments
 ///
-/// * `buf` - Buffer to be read.
-/// * `buf_len` - Buffer length.
-/// * `column_width` - column width for output.
-pub fn buf_to_array(
-    buf: &mut Read,
+/// * `bytes` - Line bytes.
+/// * `codepage` - The code page.
+pub fn codepage_gutter(bytes: &[u8], codepage: Codepage) -> String {
+    bytes.iter().map(|b| codepage_char(*b, codepage)).collect()
+}
+
+/// Minimap height in lines.
+pub const MINIMAP_HEIGHT: usize = 16;
+
+/// Average byte value of each of `height` equal buckets spanning the input.
+///
+/// # Arguments
+///
+/// * `r` - Input to scan.
+/// * `len` - Input length.
+/// * `height` - Number of buckets.
+pub fn minimap_buckets<R: Read>(r: &mut R, len: u64, height: usize) -> Result<Vec<u8>> {
+    let height = (height as u64).min(len).max(1);
+    let mut sums: Vec<u64> = vec![0; height as usize];
+    let mut counts: Vec<u64> = vec![0; height as usize];
+    let mut chunk = [0u8; 8192];
+    let mut position: u64 = 0x0;
+    loop {
+        let n = r.read(&mut chunk)?;
+        if n == 0 || position >= len {
+            break;
+        }
+        for b in chunk[..n].iter() {
+            if position >= len {
+                break;
+            }
+            let bucket = (position * height / len) as usize;
+            sums[bucket] += u64::from(*b);
+            counts[bucket] += 1;
+            position += 1;
+        }
+    }
+    Ok(sums
+        .iter()
+        .zip(counts.iter())
+        .map(|(sum, count)| if *count == 0 { 0 } else { (sum / count) as u8 })
+        .collect())
+}
+
+/// Minimap cell for a bucket average: a grayscale block when colorized,
+/// otherwise a shade glyph.
+///
+/// # Arguments
+///
+/// * `average` - Bucket average byte value.
+/// * `colorize` - Colorize output.
+pub fn minimap_cell(average: u8, colorize: bool) -> String {
+    if colorize {
+        // 232..=255 is the xterm grayscale ramp
+        let gray = 232 + (u16::from(average) * 23 / 255) as u8;
+        ansi_term::Style::new()
+            .fg(ansi_term::Color::Fixed(gray))
+            .paint("█")
+            .to_string()
+    } else {
+        let shades = [' ', '░', '▒', '▓', '█'];
+        shades[usize::from(average) * (shades.len() - 1) / 255].to_string()
+    }
+}
+
+/// Parse a number, hexadecimal with a `0x` prefix, decimal otherwise.
+///
+/// # Arguments
+///
+/// * `s` - Number to parse.
+pub fn parse_number(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if s.starts_with("0x") || s.starts_with("0X") {
+        u64::from_str_radix(&s[2..], 16).ok()
+    } else {
+        s.parse::<u64>().ok()
+    }
+}
+
+/// Parse a `START:END` byte range, end exclusive. An empty start means 0,
+/// an empty end means end of input.
+///
+/// # Arguments
+///
+/// * `s` - Range to parse.
+pub fn parse_range(s: &str) -> Option<(u64, Option<u64>)> {
+    let mut bounds = s.splitn(2, ':');
+    let start = match bounds.next()? {
+        "" => 0x0,
+        start => parse_number(start)?,
+    };
+    let end = match bounds.next()? {
+        "" => None,
+        end => Some(parse_number(end)?),
+    };
+    match end {
+        Some(end) if end < start => None,
+        _ => Some((start, end)),
+    }
+}
+
+/// Parse a gutter delimiter pair: two characters such as `||` or `<>`, or
+/// `none`.
+///
+/// # Arguments
+///
+/// * `s` - Delimiter pair to parse.
+pub fn parse_gutter_delim(s: &str) -> Option<Option<(char, char)>> {
+    if s == "none" {
+        return Some(None);
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(open), Some(close), None) => Some(Some((open, close))),
+        _ => None,
+    }
+}
+
+/// Offset nearest to `percent` of the input length.
+///
+/// # Arguments
+///
+/// * `len` - Input length, `None` when unknown (e.g. a stream).
+/// * `percent` - Position in percent of the input length.
+pub fn seek_percent_offset(len: Option<u64>, percent: f64) -> Result<u64> {
+    let len = len.ok_or_else(|| {
+        Error::Application("--seek-percent requires a seekable input of known length".to_string())
+    })?;
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(Error::Application(format!(
+            "invalid percentage '{}', expected 0 to 100",
+            percent
+        )));
+    }
+    Ok(((len as f64 * percent / 100.0).round() as u64).min(len))
+}
+
+/// Parse a list of lengths separated by commas or whitespace.
+///
+/// # Arguments
+///
+/// * `s` - Lengths to parse.
+pub fn parse_lengths(s: &str) -> Option<Vec<u64>> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|length| !length.is_empty())
+        .map(parse_number)
+        .collect()
+}
+
+/// Instruction end offsets: cumulative sums of the lengths, from `base`.
+///
+/// # Arguments
+///
+/// * `base` - Offset of the first instruction.
+/// * `lengths` - Instruction lengths.
+pub fn insn_boundaries(base: u64, lengths: &[u64]) -> Vec<u64> {
+    lengths
+        .iter()
+        .scan(base, |end, length| {
+            *end += length;
+            Some(*end)
+        })
+        .collect()
+}
+
+/// Unsigned integer from up to 8 bytes.
+///
+/// # Arguments
+///
+/// * `bytes` - Integer bytes.
+/// * `endian` - Byte order.
+pub fn read_uint(bytes: &[u8], endian: Endian) -> u64 {
+    let fold = |value: u64, b: &u8| (value << 8) | u64::from(*b);
+    match endian {
+        Endian::Big => bytes.iter().fold(0, fold),
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+    }
+}
+
+/// Parse a length field `OFFSET:WIDTH:ENDIAN`, `WIDTH` from 1 to 8 bytes
+/// and `ENDIAN` big (be) or little (le).
+///
+/// # Arguments
+///
+/// * `s` - Field to parse.
+pub fn parse_len_field(s: &str) -> Option<(u64, usize, Endian)> {
+    let mut parts = s.splitn(3, ':');
+    let at = parse_number(parts.next()?)?;
+    let width = parts
+        .next()?
+        .parse::<usize>()
+        .ok()
+        .filter(|width| (1..=8).contains(width))?;
+    let endian = parse_endian(parts.next()?)?;
+    Some((at, width, endian))
+}
+
+/// Read the unsigned length field of `width` bytes at `at`.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `at` - Offset of the field.
+/// * `width` - Field width in bytes.
+/// * `endian` - Field byte order.
+pub fn read_len_field<R: Read + Seek>(
+    r: &mut R,
+    at: u64,
+    width: usize,
+    endian: Endian,
+) -> Result<u64> {
+    let mut field = vec![0u8; width];
+    r.seek(SeekFrom::Start(at))?;
+    r.read_exact(&mut field).map_err(|_| {
+        Error::Application(format!("no {} byte length field at {}", width, offset(at)))
+    })?;
+    Ok(read_uint(&field, endian))
+}
+
+/// Parse a fixed-point format `Qm.n`, `m` integer bits including the sign
+/// and `n` fractional bits, spanning whole bytes.
+pub fn parse_fixed(s: &str) -> Option<(u32, u32)> {
+    let s = s.trim_start_matches(['Q', 'q']);
+    let mut parts = s.splitn(2, '.');
+    let m = parts.next()?.parse::<u32>().ok()?;
+    let n = parts.next()?.parse::<u32>().ok()?;
+    if m == 0 || m + n > 64 || !(m + n).is_multiple_of(8) {
+        return None;
+    }
+    Some((m, n))
+}
+
+/// Signed fixed-point value with `frac_bits` fractional bits from up to 8
+/// bytes.
+///
+/// # Arguments
+///
+/// * `bytes` - Value bytes.
+/// * `frac_bits` - Number of fractional bits.
+/// * `endian` - Byte order.
+pub fn read_fixed(bytes: &[u8], frac_bits: u32, endian: Endian) -> f64 {
+    let shift = 64 - 8 * bytes.len() as u32;
+    let raw = ((read_uint(bytes, endian) << shift) as i64) >> shift;
+    raw as f64 / 2f64.powi(frac_bits as i32)
+}
+
+/// Parse an endianness name: `big`/`be` or `little`/`le`.
+pub fn parse_endian(s: &str) -> Option<Endian> {
+    match s {
+        "big" | "be" => Some(Endian::Big),
+        "little" | "le" => Some(Endian::Little),
+        _ => None,
+    }
+}
+
+/// Parse type-length-value records, malformed records end the parse.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to parse.
+/// * `base` - Offset of the first byte.
+/// * `type_width` - Type field width in bytes.
+/// * `length_width` - Length field width in bytes.
+/// * `endian` - Byte order of the type and length fields.
+pub fn parse_tlv(
+    bytes: &[u8],
+    base: u64,
+    type_width: usize,
+    length_width: usize,
+    endian: Endian,
+) -> Vec<Tlv> {
+    let mut records: Vec<Tlv> = Vec::new();
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        let header = type_width + length_width;
+        if bytes.len() - i < header {
+            records.push(Tlv {
+                offset: base + i as u64,
+                tag: None,
+                length: None,
+                overrun: (header - (bytes.len() - i)) as u64,
+            });
+            break;
+        }
+        let tag = read_uint(&bytes[i..i + type_width], endian);
+        let length = read_uint(&bytes[i + type_width..i + header], endian);
+        let available = (bytes.len() - i - header) as u64;
+        records.push(Tlv {
+            offset: base + i as u64,
+            tag: Some(tag),
+            length: Some(length),
+            overrun: length.saturating_sub(available),
+        });
+        if length > available {
+            break;
+        }
+        i += header + length as usize;
+    }
+    records
+}
+
+/// Parse `TYPE:LENGTH` tlv field widths, each 1 to 8 bytes.
+pub fn parse_tlv_widths(s: &str) -> Option<(usize, usize)> {
+    let mut widths = s.splitn(2, ':').map(parse_number);
+    match (widths.next()?, widths.next()?) {
+        (Some(t), Some(l)) if (1..=8).contains(&t) && (1..=8).contains(&l) => {
+            Some((t as usize, l as usize))
+        }
+        _ => None,
+    }
+}
+
+/// Annotation text of a type-length-value record.
+pub fn tlv_note(tlv: &Tlv) -> String {
+    match (tlv.tag, tlv.length) {
+        (Some(tag), Some(length)) if tlv.overrun > 0 => format!(
+            "tlv type {:#x} length {} overruns input by {} bytes",
+            tag, length, tlv.overrun
+        ),
+        (Some(tag), Some(length)) => format!("tlv type {:#x} length {}", tag, length),
+        _ => format!("tlv header truncated, {} bytes missing", tlv.overrun),
+    }
+}
+
+/// Parse hex text input such as `de ad be ef`, `0xde, 0xad` or `deadbeef`,
+/// `None` unless every token is hex.
+///
+/// # Arguments
+///
+/// * `s` - Hex text to parse.
+pub fn parse_hex_input(s: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for token in s
+        .split(|c: char| c == ',' || c == ':' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+    {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if digits.is_empty() || digits.len() % 2 != 0 {
+            return None;
+        }
+        for i in (0..digits.len()).step_by(2) {
+            bytes.push(u8::from_str_radix(digits.get(i..i + 2)?, 16).ok()?);
+        }
+    }
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(bytes)
+    }
+}
+
+/// Parse a struct layout such as `u32:magic,u16:version,8s:name`: integer
+/// fields `u8` to `u64` and `i8` to `i64`, `Ns` strings of N bytes.
+///
+/// # Arguments
+///
+/// * `s` - Layout to parse.
+pub fn parse_layout(s: &str) -> Option<Vec<Field>> {
+    s.split(',')
+        .map(|field| {
+            let mut parts = field.trim().splitn(2, ':');
+            let kind = parts.next()?;
+            let name = parts.next()?.to_string();
+            let width = |bits: &str| match bits {
+                "8" => Some(1),
+                "16" => Some(2),
+                "32" => Some(4),
+                "64" => Some(8),
+                _ => None,
+            };
+            let kind = if let Some(bits) = kind.strip_prefix('u') {
+                FieldKind::Unsigned(width(bits)?)
+            } else if let Some(bits) = kind.strip_prefix('i') {
+                FieldKind::Signed(width(bits)?)
+            } else if let Some(len) = kind.strip_suffix('s') {
+                FieldKind::Str(len.parse::<usize>().ok().filter(|len| *len > 0)?)
+            } else {
+                return None;
+            };
+            Some(Field { name, kind })
+        })
+        .collect()
+}
+
+/// Decode struct fields laid out from the start of `bytes`, returning each
+/// field offset with its annotation. Fields past the end are flagged.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to decode.
+/// * `base` - Offset of the first byte.
+/// * `fields` - Struct layout.
+/// * `endian` - Byte order of integer fields.
+pub fn decode_struct(
+    bytes: &[u8],
+    base: u64,
+    fields: &[Field],
+    endian: Endian,
+) -> Vec<(u64, String)> {
+    let mut decoded: Vec<(u64, String)> = Vec::new();
+    let mut i: usize = 0;
+    for field in fields {
+        let (width, kind) = match field.kind {
+            FieldKind::Unsigned(width) => (width, format!("u{}", width * 8)),
+            FieldKind::Signed(width) => (width, format!("i{}", width * 8)),
+            FieldKind::Str(len) => (len, format!("{}s", len)),
+        };
+        let at = base + i as u64;
+        if i + width > bytes.len() {
+            decoded.push((
+                at,
+                format!("{}: {} extends past end of input", field.name, kind),
+            ));
+            break;
+        }
+        let value = &bytes[i..i + width];
+        let text = match field.kind {
+            FieldKind::Unsigned(_) => {
+                let v = read_uint(value, endian);
+                format!("{:#x} ({})", v, v)
+            }
+            FieldKind::Signed(width) => {
+                let shift = 64 - width * 8;
+                let v = ((read_uint(value, endian) << shift) as i64) >> shift;
+                format!("{}", v)
+            }
+            FieldKind::Str(_) => format!("\"{}\"", ascii_gutter(value)),
+        };
+        decoded.push((at, format!("{}: {} = {}", field.name, kind, text)));
+        i += width;
+    }
+    decoded
+}
+
+/// Check a `NAME==VALUE` or `NAME!=VALUE` assertion on a struct field.
+/// Integers compare numerically, strings against the text, quotes optional.
+///
+/// # Arguments
+///
+/// * `bytes` - Struct bytes.
+/// * `fields` - Struct layout.
+/// * `endian` - Byte order of integer fields.
+/// * `assertion` - The assertion.
+pub fn check_assertion(
+    bytes: &[u8],
+    fields: &[Field],
+    endian: Endian,
+    assertion: &str,
+) -> Result<()> {
+    let (name, expected, equal) = if let Some(at) = assertion.find("==") {
+        (&assertion[..at], &assertion[at + 2..], true)
+    } else if let Some(at) = assertion.find("!=") {
+        (&assertion[..at], &assertion[at + 2..], false)
+    } else {
+        return Err(Error::Application(format!(
+            "invalid assertion '{}', expected NAME==VALUE or NAME!=VALUE",
+            assertion
+        )));
+    };
+    let (name, expected) = (name.trim(), expected.trim());
+    let mut i: usize = 0;
+    let field = fields
+        .iter()
+        .find(|field| {
+            if field.name == name {
+                return true;
+            }
+            i += match field.kind {
+                FieldKind::Unsigned(width) | FieldKind::Signed(width) | FieldKind::Str(width) => {
+                    width
+                }
+            };
+            false
+        })
+        .ok_or_else(|| Error::Application(format!("no field '{}' in the struct layout", name)))?;
+    let invalid =
+        || Error::Application(format!("invalid value '{}' for field '{}'", expected, name));
+    let value = |width: usize| {
+        bytes
+            .get(i..i + width)
+            .ok_or_else(|| Error::Mismatch(format!("field '{}' extends past end of input", name)))
+    };
+    let (actual, matched) = match field.kind {
+        FieldKind::Unsigned(width) => {
+            let v = read_uint(value(width)?, endian);
+            (
+                format!("{:#x}", v),
+                parse_number(expected).ok_or_else(invalid)? == v,
+            )
+        }
+        FieldKind::Signed(width) => {
+            let shift = 64 - width * 8;
+            let v = ((read_uint(value(width)?, endian) << shift) as i64) >> shift;
+            let expected = match expected.strip_prefix('-') {
+                Some(magnitude) => parse_number(magnitude).map(|m| (m as i64).wrapping_neg()),
+                None => parse_number(expected).map(|v| v as i64),
+            };
+            (v.to_string(), expected.ok_or_else(invalid)? == v)
+        }
+        FieldKind::Str(len) => {
+            let v = ascii_gutter(value(len)?);
+            let matched = v == expected.trim_matches('"');
+            (format!("\"{}\"", v), matched)
+        }
+    };
+    if matched != equal {
+        return Err(Error::Mismatch(format!(
+            "assertion '{}' failed: {} is {}",
+            assertion, name, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Options a `--profile` stands for, as long argument names with their
+/// value if any.
+///
+/// # Arguments
+///
+/// * `name` - Profile name: `diff`, `compact` or `embed`.
+pub fn profile_args(name: &str) -> Option<&'static [(&'static str, Option<&'static str>)]> {
+    match name {
+        "diff" => Some(&[
+            ("color", Some("0")),
+            ("no-summary", None),
+            ("no-trailing-space", None),
+        ]),
+        "compact" => Some(&[("compact", None), ("no-summary", None)]),
+        "embed" => Some(&[("markdown", None), ("no-summary", None)]),
+        _ => None,
+    }
+}
+
+/// Expand the `--profile` of a command line into the options it stands for,
+/// inserted before the explicit arguments. An option given explicitly is
+/// left out of the expansion, so that explicit arguments take precedence.
+///
+/// # Arguments
+///
+/// * `args` - Command line, program name first.
+/// * `explicit` - Whether an option, by long name, is given explicitly.
+pub fn expand_profile<F: Fn(&str) -> bool>(args: &[String], explicit: F) -> Result<Vec<String>> {
+    let name = match args.iter().position(|arg| arg == "--profile") {
+        Some(at) => args.get(at + 1).map(String::as_str),
+        None => args.iter().find_map(|arg| arg.strip_prefix("--profile=")),
+    };
+    let name = match name {
+        Some(name) => name,
+        None => return Ok(args.to_vec()),
+    };
+    let profile = profile_args(name)
+        .ok_or_else(|| Error::Application(format!("unknown profile '{}'", name)))?;
+    let mut expanded = args[..1.min(args.len())].to_vec();
+    for (arg, value) in profile.iter().filter(|(arg, _)| !explicit(arg)) {
+        expanded.push(format!("--{}", arg));
+        expanded.extend(value.map(String::from));
+    }
+    expanded.extend(args.iter().skip(1).cloned());
+    Ok(expanded)
+}
+
+/// Names of the arguments of `app`, in their order of definition
+pub const ARGUMENT_NAMES: &[&str] = &[
+    "cols",
+    "len",
+    "format",
+    "INPUTFILE",
+    "v",
+    "color",
+    "array",
+    "func",
+    "places",
+    "record-size",
+    "record-label",
+    "auto",
+    "markdown",
+    "markdown-lang",
+    "state",
+    "resume",
+    "minimap",
+    "range",
+    "expect-fill",
+    "jsonl",
+    "gutter-delim",
+    "seek-percent",
+    "insn-lengths",
+    "offset-last",
+    "tlv",
+    "tlv-widths",
+    "endian",
+    "compact",
+    "struct",
+    "line-numbers",
+    "follow",
+    "send",
+    "codepage",
+    "null-runs",
+    "min-run",
+    "baseline",
+    "raw",
+    "extract-range",
+    "output",
+    "stats",
+    "offset-format",
+    "byte-format",
+    "zebra",
+    "strings-utf16",
+    "min-len",
+    "skip",
+    "align",
+    "no-decompress",
+    "strip-bom",
+    "watch",
+    "class-summary",
+    "repeat",
+    "mark",
+    "accent",
+    "timeout",
+    "block-hash",
+    "no-trailing-space",
+    "require-printable",
+    "plain",
+    "oneline",
+    "sep-str",
+    "until-ascii",
+    "column-hash",
+    "uuid-at",
+    "uuid-ms",
+    "hyperlinks",
+    "hyperlink-template",
+    "binary-compact",
+    "total-size",
+    "rle",
+    "rle-min",
+    "prefix",
+    "fixed",
+    "no-eof-newline",
+    "quote-strings",
+    "addr-stride",
+    "similarity",
+    "distribution-map",
+    "segments",
+    "assert",
+    "ptrs",
+    "modeline",
+    "split-on",
+    "hide-delimiter",
+    "dry-run",
+    "escape-gutter",
+    "interleave",
+    "print-page",
+    "leb128",
+    "der",
+    "alt-group-color",
+    "group-size",
+    "bitmask",
+    "map-table",
+    "diff",
+    "context",
+    "period",
+    "max-period",
+    "stop-at-null",
+    "intel-hex",
+    "srec",
+    "srec-width",
+    "checkpoint",
+    "html",
+    "tooltips",
+    "histogram",
+    "histogram-sort",
+    "ranges-file",
+    "no-summary",
+    "profile",
+    "percent",
+    "xor-with",
+    "grid",
+    "const",
+    "len-field",
+    "trim-trailing",
+    "swap-words",
+    "sort-lines",
+    "crc-delta",
+    "cmd",
+    "case",
+    "decode-column",
+    "max-memory",
+    "save-options",
+    "replay",
+    "from-clipboard",
+    "section",
+    "image",
+    "fuzzy-hash",
+];
+
+/// Options of parsed command line arguments as a key-value options file, one
+/// option per line: `KEY=VALUE` for every value, or `KEY` alone for every
+/// occurrence of a flag, long options without their dashes. The input file,
+/// `--profile`, already expanded, `--replay` and `--save-options` are left out.
+///
+/// # Arguments
+///
+/// * `matches` - Argument matches from command line.
+pub fn save_options(matches: &ArgMatches) -> String {
+    let mut names: Vec<&str> = ARGUMENT_NAMES
+        .iter()
+        .cloned()
+        .filter(|name| !["INPUTFILE", "profile", "replay", "save-options"].contains(name))
+        // defaults have no occurrence
+        .filter(|name| matches.occurrences_of(name) > 0)
+        .collect();
+    names.sort_by_key(|name| matches.index_of(name));
+
+    let mut saved = String::new();
+    for name in names {
+        // only the verbosity has no long name
+        let key = if name.len() == 1 {
+            format!("-{}", name)
+        } else {
+            name.to_string()
+        };
+        let values: Vec<_> = matches.values_of_os(name).into_iter().flatten().collect();
+        if values.is_empty() {
+            for _ in 0..matches.occurrences_of(name) {
+                saved.push_str(&format!("{}\n", key));
+            }
+        }
+        for value in values {
+            saved.push_str(&format!("{}={}\n", key, value.to_string_lossy()));
+        }
+    }
+    saved
+}
+
+/// Write the options of a command line to an options file, see
+/// `save_options`.
+///
+/// # Arguments
+///
+/// * `path` - Options file path.
+/// * `matches` - Argument matches from command line.
+pub fn write_saved_options(path: &str, matches: &ArgMatches) -> Result<()> {
+    fs::write(path, save_options(matches))?;
+    Ok(())
+}
+
+/// Options of an options file written by `save_options`, as their names and
+/// command line arguments, e.g. `("cols", "--cols=8")`. Blank lines and lines
+/// starting with `#` are skipped.
+///
+/// # Arguments
+///
+/// * `text` - Content of the options file.
+pub fn replayed_args(text: &str) -> Vec<(String, String)> {
+    let mut args = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let key = &line[..line.find('=').unwrap_or(line.len())];
+        let name = key.trim_start_matches('-').to_string();
+        if key.starts_with('-') {
+            args.push((name, line.to_string()));
+        } else {
+            args.push((name, format!("--{}", line)));
+        }
+    }
+    args
+}
+
+/// Expand the `--replay` options file of a command line into its options,
+/// inserted before the explicit arguments. An option given explicitly is
+/// left out of the replay, so that explicit arguments take precedence.
+///
+/// # Arguments
+///
+/// * `args` - Command line, program name first.
+/// * `explicit` - Whether an option, by name, is given explicitly.
+pub fn replay_options<F: Fn(&str) -> bool>(args: &[String], explicit: F) -> Result<Vec<String>> {
+    let path = match args.iter().position(|arg| arg == "--replay") {
+        Some(at) => args.get(at + 1).map(String::as_str),
+        None => args.iter().find_map(|arg| arg.strip_prefix("--replay=")),
+    };
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(args.to_vec()),
+    };
+    let mut expanded = args[..1.min(args.len())].to_vec();
+    for (name, arg) in replayed_args(&fs::read_to_string(path)?) {
+        if !explicit(&name) {
+            expanded.push(arg);
+        }
+    }
+    expanded.extend(args.iter().skip(1).cloned());
+    Ok(expanded)
+}
+
+/// Run a command line: expand its `--replay` options file and its
+/// `--profile`, and save its options with `--save-options`, before `run`.
+///
+/// # Arguments
+///
+/// * `args` - Command line, program name first.
+pub fn run_args(args: &[String]) -> Result<()> {
+    let explicit = app().get_matches_from(args);
+    let given = |arg: &str| explicit.occurrences_of(arg) > 0;
+    let args = expand_profile(&replay_options(args, given)?, given)?;
+    let matches = app().get_matches_from(&args);
+    if let Some(path) = matches.value_of("save-options") {
+        write_saved_options(path, &matches)?;
+    }
+    run(matches)
+}
+
+/// Parse an optional numeric argument.
+///
+/// # Arguments
+///
+/// * `matches` - Argument matches from command line.
+/// * `name` - Argument name.
+pub fn parse_u64_arg(matches: &ArgMatches, name: &str) -> Result<Option<u64>> {
+    match matches.value_of(name) {
+        Some(value) => parse_number(value)
+            .map(Some)
+            .ok_or_else(|| Error::Application(format!("invalid value '{}' for --{}", value, name))),
+        None => Ok(None),
+    }
+}
+
+/// Canonical string of a 16-byte UUID. With `mixed_endian`, the first three
+/// groups are read little-endian as in Microsoft GUIDs.
+///
+/// # Arguments
+///
+/// * `bytes` - UUID bytes, as stored.
+/// * `mixed_endian` - Microsoft GUID byte order.
+pub fn format_uuid(bytes: &[u8; 16], mixed_endian: bool) -> String {
+    let mut b = *bytes;
+    if mixed_endian {
+        b[0..4].reverse();
+        b[4..6].reverse();
+        b[6..8].reverse();
+    }
+    let hex: Vec<String> = b.iter().map(|x| format!("{:02x}", x)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex[0..4].concat(),
+        hex[4..6].concat(),
+        hex[6..8].concat(),
+        hex[8..10].concat(),
+        hex[10..16].concat()
+    )
+}
+
+/// Decode the LEB128 integer at the start of `bytes`, as its unsigned and
+/// signed values and the number of bytes it takes. `None` if it does not
+/// end within `bytes` or exceeds 64 bits.
+///
+/// # Arguments
+///
+/// * `bytes` - Encoded bytes.
+pub fn decode_leb128(bytes: &[u8]) -> Option<(u64, i64, usize)> {
+    let mut value: u64 = 0;
+    for (i, b) in bytes.iter().enumerate().take(10) {
+        let shift = 7 * i as u32;
+        value |= u64::from(b & 0x7f) << shift;
+        if b & 0x80 == 0 {
+            let bits = shift + 7;
+            let signed = if bits < 64 && b & 0x40 != 0 {
+                (value | (!0u64 << bits)) as i64
+            } else {
+                value as i64
+            };
+            return Some((value, signed, i + 1));
+        }
+    }
+    None
+}
+
+/// Identifier and length octets of an ASN.1 DER/BER tag-length-value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DerHeader {
+    /// tag class: universal, application, context or private
+    pub class: &'static str,
+    /// whether the value holds nested tag-length-values
+    pub constructed: bool,
+    /// tag number
+    pub tag: u64,
+    /// value length, `None` for the indefinite form
+    pub length: Option<u64>,
+    /// whether the length uses the long form
+    pub long_form: bool,
+    /// number of identifier and length bytes
+    pub header_len: usize,
+}
+
+/// Decode the tag and length of the DER tag-length-value at the start of
+/// `bytes`, high tag numbers, long-form and indefinite lengths included.
+/// `None` if the header does not end within `bytes` or is malformed.
+///
+/// # Arguments
+///
+/// * `bytes` - Encoded bytes.
+pub fn decode_der_header(bytes: &[u8]) -> Option<DerHeader> {
+    let identifier = *bytes.first()?;
+    let class = ["universal", "application", "context", "private"][(identifier >> 6) as usize];
+    let mut at = 1;
+    let mut tag = u64::from(identifier & 0x1f);
+    if tag == 0x1f {
+        let (number, len) = decode_base128(&bytes[at..])?;
+        tag = number;
+        at += len;
+    }
+    let first = *bytes.get(at)?;
+    at += 1;
+    let (length, long_form) = match first {
+        0x80 => (None, false),
+        0xff => return None,
+        n if n & 0x80 != 0 => {
+            let n = (n & 0x7f) as usize;
+            if n > 8 || bytes.len() < at + n {
+                return None;
+            }
+            let length = bytes[at..at + n]
+                .iter()
+                .fold(0u64, |length, b| length << 8 | u64::from(*b));
+            at += n;
+            (Some(length), true)
+        }
+        n => (Some(u64::from(n)), false),
+    };
+    Some(DerHeader {
+        class,
+        constructed: identifier & 0x20 != 0,
+        tag,
+        length,
+        long_form,
+        header_len: at,
+    })
+}
+
+/// Decode a big-endian base-128 number, as used by high ASN.1 tag numbers,
+/// with the number of bytes it takes.
+fn decode_base128(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    for (i, b) in bytes.iter().enumerate().take(9) {
+        value = value << 7 | u64::from(b & 0x7f);
+        if b & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Name of a universal ASN.1 tag number.
+///
+/// # Arguments
+///
+/// * `tag` - Universal tag number.
+pub fn der_universal_name(tag: u64) -> Option<&'static str> {
+    Some(match tag {
+        1 => "BOOLEAN",
+        2 => "INTEGER",
+        3 => "BIT STRING",
+        4 => "OCTET STRING",
+        5 => "NULL",
+        6 => "OBJECT IDENTIFIER",
+        12 => "UTF8String",
+        16 => "SEQUENCE",
+        17 => "SET",
+        19 => "PrintableString",
+        22 => "IA5String",
+        23 => "UTCTime",
+        24 => "GeneralizedTime",
+        _ => return None,
+    })
+}
+
+/// Annotation of a DER tag-length-value at `at`: its tag, length and the
+/// end of the bytes it takes.
+///
+/// # Arguments
+///
+/// * `header` - Decoded header.
+/// * `at` - Offset of the identifier byte.
+pub fn der_annotation(header: &DerHeader, at: u64) -> String {
+    let name = match der_universal_name(header.tag) {
+        Some(name) if header.class == "universal" => name.to_string(),
+        _ => format!("[{}]", header.tag),
+    };
+    let kind = if header.constructed {
+        "constructed"
+    } else {
+        "primitive"
+    };
+    match header.length {
+        Some(length) => format!(
+            "der {} {}, {}, length {}{}, up to {}",
+            header.class,
+            name,
+            kind,
+            length,
+            if header.long_form { " (long form)" } else { "" },
+            offset(at + header.header_len as u64 + length)
+        ),
+        None => format!("der {} {}, {}, indefinite length", header.class, name, kind),
+    }
+}
+
+/// Wrap `text` in an OSC 8 terminal hyperlink to `target`.
+///
+/// # Arguments
+///
+/// * `target` - Link target.
+/// * `text` - Link text.
+pub fn hyperlink(target: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", target, text)
+}
+
+/// Offset hyperlink template of `--hyperlinks`: the given template, or by
+/// default a link into the input file. Only a dump written to the terminal
+/// gets links, and by default only if its input is a file.
+///
+/// # Arguments
+///
+/// * `matches` - Argument matches from command line.
+/// * `to_terminal` - Whether the dump goes to the terminal.
+pub fn hyperlink_template(matches: &ArgMatches, to_terminal: bool) -> Option<String> {
+    if !matches.is_present("hyperlinks") || !to_terminal {
+        return None;
+    }
+    match matches.value_of("hyperlink-template") {
+        Some(template) => Some(template.to_string()),
+        None => matches
+            .value_of("INPUTFILE")
+            .filter(|file| *file != "-")
+            .and_then(|file| fs::canonicalize(file).ok())
+            .filter(|path| path.is_file())
+            .map(|path| format!("file://{}#{{offset}}", path.display())),
+    }
+}
+
+/// Record header, substituting `{n}` with the record index and `{offset}`
+/// with the record start offset, formatted as in the offset column.
+///
+/// # Arguments
+///
+/// * `template` - Label template.
+/// * `n` - Record index.
+/// * `at` - Formatted record start offset.
+pub fn record_label(template: &str, n: u64, at: &str) -> String {
+    template
+        .replace("{n}", &n.to_string())
+        .replace("{offset}", at)
+}
+
+/// Split page lines so that no line straddles a record boundary, each record
+/// starting its own column grid.
+///
+/// # Arguments
+///
+/// * `page` - Page to split.
+/// * `record_size` - Record size in bytes.
+/// * `column_width` - column width for output.
+pub fn split_records(page: Page, record_size: u64, column_width: u64) -> Page {
+    let mut records: Page = Page::new();
+    records.offset = page.offset;
+    records.bytes = page.bytes;
+    let mut split: Line = Line::new();
+    for line in page.body {
+        for (i, b) in line.hex_body.into_iter().enumerate() {
+            let b_offset = line.offset + i as u64;
+            if (b_offset % record_size).is_multiple_of(column_width) && split.bytes > 0 {
+                records.body.push(split);
+                split = Line::new();
+            }
+            if split.bytes == 0 {
+                split.offset = b_offset;
+            }
+            split.hex_body.push(b);
+            split.bytes += 1;
+        }
+    }
+    if split.bytes > 0 {
+        records.body.push(split);
+    }
+    records
+}
+
+/// Records of `bytes` each ending with a `delimiter` byte, the last one
+/// possibly without, as `(start, length)` with the delimiter counted.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to split.
+/// * `base` - Offset of the first byte.
+/// * `delimiter` - Byte ending a record.
+pub fn delimited_records(bytes: &[u8], base: u64, delimiter: u8) -> Vec<(u64, u64)> {
+    let mut start = base;
+    bytes
+        .split_inclusive(|b| *b == delimiter)
+        .map(|record| {
+            let at = start;
+            start += record.len() as u64;
+            (at, record.len() as u64)
+        })
+        .collect()
+}
+
+/// Page without the delimiter bytes of `delimited_records`, each the last
+/// byte of its line once records are split, and without emptied lines.
+///
+/// # Arguments
+///
+/// * `page` - Page split on the records.
+/// * `delimiter` - Byte ending a record.
+pub fn drop_delimiters(mut page: Page, delimiter: u8) -> Page {
+    for line in page.body.iter_mut() {
+        if line.hex_body.last() == Some(&delimiter) {
+            line.hex_body.pop();
+            line.bytes -= 1;
+        }
+    }
+    page.body.retain(|line| line.bytes > 0);
+    page
+}
+
+/// Page of the bytes of `page` repeated `times` in sequence, offsets
+/// continuing as if the input were concatenated with itself.
+///
+/// # Arguments
+///
+/// * `page` - Page to repeat.
+/// * `times` - Number of repetitions, at least 1.
+/// * `column_width` - column width for output.
+pub fn repeat_page(page: Page, times: u64, column_width: u64) -> Result<Page> {
+    if times <= 1 {
+        return Ok(page);
+    }
+    let len = page
+        .bytes
+        .checked_mul(times)
+        .filter(|len| *len <= MAX_PAGE_LEN)
+        .ok_or_else(|| {
+            Error::Application(format!(
+                "{} repetitions exceed the {}MB in-memory limit",
+                times,
+                MAX_PAGE_LEN >> 20
+            ))
+        })?;
+    let bytes = page.to_bytes().repeat(times as usize);
+    buf_to_page(&mut io::Cursor::new(bytes), page.offset, len, column_width)
+}
+
+/// Number of bytes before the first zero byte within `len` bytes of `r`,
+/// `len` if there is none.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `len` - Number of bytes to scan.
+pub fn len_to_null<R: BufRead>(r: &mut R, len: u64) -> Result<u64> {
+    let mut scanned: u64 = 0;
+    let mut r = r.take(len);
+    loop {
+        let chunk = r.fill_buf()?;
+        if chunk.is_empty() {
+            return Ok(scanned);
+        }
+        if let Some(at) = chunk.iter().position(|b| *b == 0) {
+            return Ok(scanned + at as u64);
+        }
+        let n = chunk.len();
+        scanned += n as u64;
+        r.consume(n);
+    }
+}
+
+/// Page lines up to the first one where the ascii gutter, read across
+/// lines, contains `marker`. The whole page if the marker never appears.
+///
+/// # Arguments
+///
+/// * `page` - Page to cut.
+/// * `marker` - Text to stop at.
+pub fn until_ascii(mut page: Page, marker: &str) -> Page {
+    let mut gutter = String::new();
+    let found = page.body.iter().position(|line| {
+        gutter.push_str(&ascii_gutter(&line.hex_body));
+        gutter.contains(marker)
+    });
+    if let Some(last) = found {
+        page.body.truncate(last + 1);
+        page.bytes = page.body.iter().map(|line| line.bytes).sum();
+    }
+    page
+}
+
+/// CRC-32 of every page line, with `=` when it equals the CRC of the line
+/// before, `\u{2191}` when greater, `\u{2193}` when smaller, `-` on the first line.
+///
+/// # Arguments
+///
+/// * `page` - Page to checksum.
+pub fn crc_deltas(page: &Page) -> Vec<(u32, char)> {
+    let mut previous: Option<u32> = None;
+    page.body
+        .iter()
+        .map(|line| {
+            let crc = crc32(&line.hex_body);
+            let delta = match previous {
+                None => '-',
+                Some(before) if crc == before => '=',
+                Some(before) if crc > before => '\u{2191}',
+                Some(_) => '\u{2193}',
+            };
+            previous = Some(crc);
+            (crc, delta)
+        })
+        .collect()
+}
+
+/// Estimated memory, in bytes, of a page holding `len` bytes in lines of
+/// `column_width` bytes.
+///
+/// # Arguments
+///
+/// * `len` - Number of bytes of the page.
+/// * `column_width` - Bytes per line.
+pub fn page_memory(len: u64, column_width: u64) -> u64 {
+    let lines = len.div_ceil(column_width.max(1));
+    len.saturating_add(lines.saturating_mul(size_of::<Line>() as u64))
+}
+
+/// Fail if buffering `needed` bytes exceeds `max_memory`, when given.
+///
+/// # Arguments
+///
+/// * `needed` - Memory the dump would buffer.
+/// * `max_memory` - Memory limit, if any.
+pub fn check_memory(needed: u64, max_memory: Option<u64>) -> Result<()> {
+    match max_memory {
+        Some(max_memory) if needed > max_memory => Err(Error::Application(format!(
+            "the dump would buffer {} bytes, over the --max-memory limit of {}; \
+             dump a part with --len or --range",
+            needed, max_memory
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Order page lines by their bytes, so that reordered but identical
+/// content compares equal. Lines keep their offsets.
+///
+/// # Arguments
+///
+/// * `page` - Page to sort.
+pub fn sort_lines(page: &mut Page) {
+    page.body.sort_by(|a, b| a.hex_body.cmp(&b.hex_body));
+}
+
+/// Index of the byte shown at `i` of a line of `len` bytes when the bytes
+/// of every `group` are reversed. A trailing partial group is unchanged.
+///
+/// # Arguments
+///
+/// * `i` - Display position in the line.
+/// * `len` - Number of bytes of the line.
+/// * `group` - Group width in bytes, `None` to keep the order.
+pub fn swapped_index(i: usize, len: usize, group: Option<u64>) -> usize {
+    match group.map(|group| group as usize).filter(|group| *group > 1) {
+        Some(group) if i - i % group + group <= len => i - i % group + group - 1 - i % group,
+        _ => i,
+    }
+}
+
+/// Keep only the first line of the run of identical lines ending the page,
+/// e.g. trailing padding. Returns the number of lines left out, the page
+/// byte count is unchanged.
+///
+/// # Arguments
+///
+/// * `page` - Page to trim.
+pub fn trim_trailing(page: &mut Page) -> usize {
+    let last = match page.body.last() {
+        Some(last) => last.hex_body.clone(),
+        None => return 0,
+    };
+    let run = page
+        .body
+        .iter()
+        .rev()
+        .take_while(|line| line.hex_body == last)
+        .count();
+    let omitted = run - 1;
+    page.body.truncate(page.body.len() - omitted);
+    omitted
+}
+
+/// Split page lines on a column grid starting at the `align` boundary at or
+/// below the page offset, the first line leading with empty columns.
+///
+/// # Arguments
+///
+/// * `page` - Page to split.
+/// * `align` - Alignment of the grid start in bytes.
+/// * `column_width` - column width for output.
+pub fn align_page(page: Page, align: u64, column_width: u64) -> Page {
+    let grid_start = page.offset - page.offset % align;
+    let mut aligned: Page = Page::new();
+    aligned.offset = page.offset;
+    aligned.bytes = page.bytes;
+    let mut split: Line = Line::new();
+    for line in page.body {
+        for (i, b) in line.hex_body.into_iter().enumerate() {
+            let b_offset = line.offset + i as u64;
+            if (b_offset - grid_start).is_multiple_of(column_width) && split.bytes > 0 {
+                aligned.body.push(split);
+                split = Line::new();
+            }
+            if split.bytes == 0 {
+                split.offset = b_offset;
+                if aligned.body.is_empty() {
+                    split.lead = (b_offset - grid_start) % column_width;
+                }
+            }
+            split.hex_body.push(b);
+            split.bytes += 1;
+        }
+    }
+    if split.bytes > 0 {
+        aligned.body.push(split);
+    }
+    aligned
+}
+
+/// Split page lines at the given offsets, each split starting its own column
+/// grid.
+///
+/// # Arguments
+///
+/// * `page` - Page to split.
+/// * `boundaries` - Sorted offsets starting a new line.
+/// * `column_width` - column width for output.
+pub fn split_at(page: Page, boundaries: &[u64], column_width: u64) -> Page {
+    let mut splits: Page = Page::new();
+    splits.offset = page.offset;
+    splits.bytes = page.bytes;
+    let mut split: Line = Line::new();
+    let mut grid_start: u64 = page.offset;
+    let mut next = boundaries.iter().peekable();
+    for line in page.body {
+        for (i, b) in line.hex_body.into_iter().enumerate() {
+            let b_offset = line.offset + i as u64;
+            while next.peek().is_some_and(|boundary| **boundary < b_offset) {
+                next.next();
+            }
+            if next.peek() == Some(&&b_offset) {
+                grid_start = b_offset;
+            }
+            if (b_offset - grid_start).is_multiple_of(column_width) && split.bytes > 0 {
+                splits.body.push(split);
+                split = Line::new();
+            }
+            if split.bytes == 0 {
+                split.offset = b_offset;
+            }
+            split.hex_body.push(b);
+            split.bytes += 1;
+        }
+    }
+    if split.bytes > 0 {
+        splits.body.push(split);
+    }
+    splits
+}
+
+/// Whether the bytes of a line differ from the baseline at the same offset.
+///
+/// # Arguments
+///
+/// * `line` - The line.
+/// * `baseline` - Earlier content of the input.
+pub fn line_changed(line: &Line, baseline: &[u8]) -> bool {
+    let start = line.offset as usize;
+    let end = start + line.hex_body.len();
+    baseline.get(start..end) != Some(&line.hex_body[..])
+}
+
+/// Line index ranges of the changed lines of a page, each widened by
+/// `context` lines on both sides, overlapping ranges merged.
+///
+/// # Arguments
+///
+/// * `page` - Page to compare.
+/// * `baseline` - Earlier content of the input.
+/// * `context` - Unchanged lines kept around each change.
+pub fn diff_hunks(page: &Page, baseline: &[u8], context: usize) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (row, line) in page.body.iter().enumerate() {
+        if !line_changed(line, baseline) {
+            continue;
+        }
+        let (start, end) = (
+            row.saturating_sub(context),
+            (row + context + 1).min(page.body.len()),
+        );
+        match hunks.last_mut() {
+            Some(last) if last.1 >= start => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    hunks
+}
+
+/// Print only the changed lines of a page and their context, each hunk
+/// under a `@@ START..END @@` header giving its byte range.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `options` - Dump options, with the baseline to compare against.
+/// * `context` - Unchanged lines kept around each change.
+/// * `w` - Output writer.
+pub fn print_hunks<W: Write>(
+    page: &Page,
+    options: &Options,
+    context: usize,
+    w: &mut W,
+) -> Result<()> {
+    let baseline = options.baseline.as_deref().unwrap_or_default();
+    for (start, end) in diff_hunks(page, baseline, context) {
+        let (first, last) = (&page.body[start], &page.body[end - 1]);
+        writeln!(
+            w,
+            "@@ {}..{} @@",
+            offset(first.offset),
+            offset(last.offset + last.bytes)
+        )?;
+        for row in start..end {
+            print_line(&page.body[row], row, options, w)?;
+        }
+    }
+    if !options.no_summary {
+        writeln!(w, "   bytes: {}", page.bytes)?;
+    }
+    Ok(())
+}
+
+/// Print page lines: offset, octets and ascii text.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `options` - Dump options.
+/// * `w` - Output writer.
+pub fn print_page<W: Write>(page: &Page, options: &Options, w: &mut W) -> Result<()> {
+    for (row, line) in page.body.iter().enumerate() {
+        print_line(line, row, options, w)?;
+    }
+    if options.trimmed_lines > 0 {
+        writeln!(w, "* ({} identical lines omitted)", options.trimmed_lines)?;
+    }
+    if !options.no_summary {
+        writeln!(w, "   bytes: {}", page.bytes)?;
+    }
+    Ok(())
+}
+
+/// Print page lines, each byte styled by `styler` from its offset and value,
+/// unstyled where it returns `None`.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `styler` - Style of the byte at an offset.
+/// * `w` - Output writer.
+pub fn dump_with_styler<W, F>(page: &Page, styler: F, w: &mut W) -> Result<()>
+where
+    W: Write,
+    F: Fn(u64, u8) -> Option<ansi_term::Style>,
+{
+    let options = Options::new();
+    for (row, line) in page.body.iter().enumerate() {
+        print_line_styled(line, row, &options, &styler, w)?;
+    }
+    writeln!(w, "   bytes: {}", page.bytes)?;
+    Ok(())
+}
+
+/// Print a single line: record header if any, offset, octets and ascii text.
+///
+/// # Arguments
+///
+/// * `line` - Line to print.
+/// * `row` - Line index within the dump.
+/// * `options` - Dump options.
+/// * `w` - Output writer.
+pub fn print_line<W: Write>(line: &Line, row: usize, options: &Options, w: &mut W) -> Result<()> {
+    let styler = |at: u64, b: u8| {
+        if !options.colorize {
+            None
+        } else if is_marked(&options.marks, at) {
+            Some(options.accent.bold())
+        } else if let Some(group_size) = options.alt_group {
+            Some(group_style(line.lead + at - line.offset, group_size))
+        } else {
+            Some(byte_style(b))
+        }
+    };
+    print_line_styled(line, row, options, &styler, w)
+}
+
+/// Print a single line as `print_line` does, each byte styled by `styler`.
+///
+/// # Arguments
+///
+/// * `line` - Line to print.
+/// * `row` - Line index within the dump.
+/// * `options` - Dump options.
+/// * `styler` - Style of the byte at an offset.
+/// * `w` - Output writer.
+fn print_line_styled<W, F>(
+    line: &Line,
+    row: usize,
+    options: &Options,
+    styler: &F,
+    w: &mut W,
+) -> Result<()>
+where
+    W: Write,
+    F: Fn(u64, u8) -> Option<ansi_term::Style>,
+{
+    // offsets are shown relative to their delimited record, and scaled
+    let address = |at: u64| {
+        let relative = match options.record_starts.partition_point(|start| *start <= at) {
+            0 => at,
+            i => at - options.record_starts[i - 1],
+        };
+        relative * options.addr_stride
+    };
+
+    if let (Some(size), Some(template)) = (options.record_size, options.record_label.as_ref()) {
+        if line.offset.is_multiple_of(size) {
+            let at = format_offset(
+                address(line.offset),
+                options.offset_format,
+                !options.compact,
+            );
+            writeln!(w, "{}", record_label(template, line.offset / size, &at))?;
+        }
+    }
+
+    for (at, notes) in options
+        .annotations
+        .range(line.offset..line.offset + line.bytes.max(1))
+    {
+        for note in notes {
+            writeln!(w, "; {}: {}", offset(*at), note)?;
+        }
+    }
+
+    let label = if options.offset_last && line.bytes > 0 {
+        line.offset + line.bytes - 1
+    } else {
+        line.offset - line.lead
+    };
+    let label = address(label);
+    let stripe = if options.zebra && options.colorize && row % 2 == 1 {
+        Some(ansi_term::Style::new().on(ZEBRA_BACKGROUND))
+    } else {
+        None
+    };
+    if let Some(stripe) = stripe {
+        write!(w, "{}", stripe.prefix())?;
+    }
+    if let Some(ref baseline) = options.baseline {
+        write!(
+            w,
+            "{} ",
+            if line_changed(line, baseline) {
+                '|'
+            } else {
+                ' '
+            }
+        )?;
+    }
+    if options.line_numbers {
+        write!(w, "{}: ", row + 1)?;
+    }
+    if let Some(total) = options.percent_of.filter(|total| *total > 0) {
+        write!(w, "{:>3}% ", line.offset.min(total) * 100 / total)?;
+    }
+    let text = format_offset(label, options.offset_format, !options.compact);
+    let text = match options.hyperlink {
+        Some(ref template) => hyperlink(
+            &template.replace("{offset}", &format!("{:#x}", label)),
+            &text,
+        ),
+        None => text,
+    };
+    if options.compact {
+        write!(w, "{} ", text)?;
+    } else {
+        write!(w, "{}: ", text)?;
+    }
+
+    let byte_width = format_byte(0, options.format, !options.compact).len();
+    write!(w, "{:<1$}", "", (byte_width + 1) * line.lead as usize)?;
+    // with no_trailing_space, the separator after the last byte is held
+    // back until something follows it on the line
+    let mut held_sep: Option<char> = None;
+    for i in 0..line.hex_body.len() {
+        let source = swapped_index(i, line.hex_body.len(), options.swap_words);
+        let hex = &line.hex_body[source];
+        let end = line.offset + i as u64 + 1;
+        let sep = if options.insn_boundaries.binary_search(&end).is_ok() {
+            '|'
+        } else {
+            ' '
+        };
+        let text = format_byte(*hex, options.format, !options.compact);
+        let style = styler(line.offset + source as u64, *hex);
+        let (sep, held) = if options.no_trailing_space && i + 1 == line.hex_body.len() {
+            (String::new(), Some(sep))
+        } else {
+            (sep.to_string(), None)
+        };
+        held_sep = held;
+        match (stripe, style) {
+            (Some(stripe), Some(style)) => {
+                // the paint resets the stripe background, restart it
+                let style = style.on(ZEBRA_BACKGROUND);
+                write!(w, "{}{}{}", style.paint(text), stripe.prefix(), sep)?;
+            }
+            (None, Some(style)) => write!(w, "{}{}", style.paint(text), sep)?,
+            (_, None) => write!(w, "{}{}", text, sep)?,
+        }
+    }
+
+    let mut ascii_string: String = if options.pointers {
+        pointer_gutter(&line.hex_body)
+    } else if options.quote_strings {
+        quoted_gutter(&line.hex_body)
+    } else if let Some(codepage) = options.codepage {
+        codepage_gutter(&line.hex_body, codepage)
+    } else if options.utf8_gutter {
+        utf8_gutter(&line.hex_body)
+    } else {
+        ascii_gutter(&line.hex_body)
+    };
+    if options.escape_gutter {
+        ascii_string = escape_gutter(&ascii_string, options.gutter_delim);
+    }
+    ascii_string.insert_str(0, &" ".repeat(line.lead as usize));
+    let mut gutter_width = options.column_width as usize;
+    if let Some((open, close)) = options.gutter_delim {
+        ascii_string = format!("{}{}{}", open, ascii_string, close);
+        gutter_width += 2;
+    }
+    if options.bitmask {
+        let columns = ascii_string.chars().count();
+        let bits: Vec<String> = line.hex_body.iter().map(|b| set_bits(*b)).collect();
+        ascii_string = format!(
+            "{}{:<2$} {3}",
+            ascii_string,
+            "",
+            gutter_width.saturating_sub(columns),
+            bits.join(" ")
+        );
+    }
+    if let Some(decoding) = options.decode_column {
+        let columns = ascii_string.chars().count();
+        ascii_string = format!(
+            "{}{:<2$} {3}",
+            ascii_string,
+            "",
+            gutter_width.saturating_sub(columns),
+            decode_gutter(&ascii_gutter(&line.hex_body), decoding)
+        );
+    }
+    if let Some((crc, delta)) = options.crc_deltas.get(row) {
+        let columns = ascii_string.chars().count();
+        ascii_string = format!(
+            "{}{:<2$} crc {3:08x} {4}",
+            ascii_string,
+            "",
+            gutter_width.saturating_sub(columns),
+            crc,
+            delta
+        );
+    }
+    let minimap = options.minimap.get(row);
+    if options.no_trailing_space && minimap.is_none() {
+        ascii_string.truncate(ascii_string.trim_end().len());
+    }
+    let trailing = options.no_trailing_space && minimap.is_none() && ascii_string.is_empty();
+
+    if let Some(sep) = held_sep.filter(|sep| !trailing || *sep != ' ') {
+        write!(w, "{}", sep)?;
+    }
+    if line.lead + line.bytes < options.column_width && !trailing {
+        write!(
+            w,
+            "{:<1$}",
+            "",
+            (byte_width + 1) * (options.column_width - line.lead - line.bytes) as usize
+        )?;
+    }
+
+    let stripe_end = stripe
+        .map(|stripe| stripe.suffix().to_string())
+        .unwrap_or_default();
+    if let Some(average) = minimap {
+        write!(
+            w,
+            "{:<3$}{} {}",
+            ascii_string,
+            stripe_end,
+            minimap_cell(*average, options.colorize),
+            gutter_width
+        )?;
+        writeln!(w)?;
+    } else {
+        writeln!(w, "{}{}", ascii_string, stripe_end)?; // print ascii string
+    }
+    Ok(())
+}
+
+/// Print the lines of two pages in turn, each labeled with its source, so
+/// that the bytes at the same offsets are adjacent.
+///
+/// # Arguments
+///
+/// * `a` - Page of the first input.
+/// * `b` - Page of the second input, at the same offset.
+/// * `options` - Dump options.
+/// * `w` - Output writer.
+pub fn print_interleaved<W: Write>(a: &Page, b: &Page, options: &Options, w: &mut W) -> Result<()> {
+    for row in 0..a.body.len().max(b.body.len()) {
+        if let Some(line) = a.body.get(row) {
+            print_line(line, row, options, &mut Prefixed::new(&mut *w, "A "))?;
+        }
+        if let Some(line) = b.body.get(row) {
+            print_line(line, row, options, &mut Prefixed::new(&mut *w, "B "))?;
+        }
+    }
+    if !options.no_summary {
+        writeln!(w, "   bytes: A {}, B {}", a.bytes, b.bytes)?;
+    }
+    Ok(())
+}
+
+/// Whether the locale announces utf-8, so that box-drawing characters
+/// display.
+pub fn unicode_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+}
+
+/// Print a page framed as a table: a border around the dump, and rules
+/// between the offset, hex and gutter columns on every line. Cells are not
+/// colored, box-drawing characters fall back to ascii without `unicode`.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `options` - Dump options.
+/// * `unicode` - Draw with box-drawing characters.
+/// * `w` - Output writer.
+pub fn print_grid<W: Write>(
+    page: &Page,
+    options: &Options,
+    unicode: bool,
+    w: &mut W,
+) -> Result<()> {
+    let [horizontal, vertical, top, bottom] = if unicode {
+        [
+            "\u{2500}",
+            "\u{2502}",
+            "\u{250c}\u{252c}\u{2510}",
+            "\u{2514}\u{2534}\u{2518}",
+        ]
+    } else {
+        ["-", "|", "+++", "+++"]
+    };
+    let prefix = !options.compact;
+    let offset_width = page
+        .body
+        .iter()
+        .map(|line| format_offset(line.offset, options.offset_format, prefix).len())
+        .max()
+        .unwrap_or(0);
+    let columns = options.column_width as usize;
+    let byte_width = format_byte(0, options.format, prefix).len();
+    let hex_width = (columns * (byte_width + 1)).saturating_sub(1);
+    let rule = |corners: &str| -> String {
+        let corners: Vec<char> = corners.chars().collect();
+        format!(
+            "{}{}{}{}{}{}{}",
+            corners[0],
+            horizontal.repeat(offset_width + 2),
+            corners[1],
+            horizontal.repeat(hex_width + 2),
+            corners[1],
+            horizontal.repeat(columns + 2),
+            corners[2]
+        )
+    };
+    writeln!(w, "{}", rule(top))?;
+    for line in &page.body {
+        let hex: Vec<String> = line
+            .hex_body
+            .iter()
+            .map(|b| format_byte(*b, options.format, prefix))
+            .collect();
+        writeln!(
+            w,
+            "{v} {:<ow$} {v} {:<hw$} {v} {:<cw$} {v}",
+            format_offset(line.offset, options.offset_format, prefix),
+            hex.join(" "),
+            ascii_gutter(&line.hex_body),
+            v = vertical,
+            ow = offset_width,
+            hw = hex_width,
+            cw = columns
+        )?;
+    }
+    writeln!(w, "{}", rule(bottom))?;
+    if !options.no_summary {
+        writeln!(w, "   bytes: {}", page.bytes)?;
+    }
+    Ok(())
+}
+
+/// Print a page for hard copy: every `lines_per_page` lines form a printed
+/// page with a header and a footer, printed pages separated by form feeds.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `options` - Dump options.
+/// * `lines_per_page` - Dump lines per printed page.
+/// * `title` - Header title, e.g. the input name.
+/// * `w` - Output writer.
+pub fn print_paginated<W: Write>(
+    page: &Page,
+    options: &Options,
+    lines_per_page: usize,
+    title: &str,
+    w: &mut W,
+) -> Result<()> {
+    let lines_per_page = lines_per_page.max(1);
+    let pages = page.body.len().div_ceil(lines_per_page).max(1);
+    for n in 0..pages {
+        if n > 0 {
+            write!(w, "\x0c")?;
+        }
+        writeln!(w, "{}, page {} of {}", title, n + 1, pages)?;
+        writeln!(w)?;
+        let rows = n * lines_per_page..((n + 1) * lines_per_page).min(page.body.len());
+        for row in rows {
+            print_line(&page.body[row], row, options, w)?;
+        }
+        writeln!(w)?;
+        writeln!(w, "-- {} --", n + 1)?;
+    }
+    if !options.no_summary {
+        writeln!(w, "   bytes: {}", page.bytes)?;
+    }
+    Ok(())
+}
+
+/// Count the bytes of each class.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to classify.
+pub fn class_counts(bytes: &[u8]) -> ClassCounts {
+    let mut counts = ClassCounts::default();
+    for b in bytes {
+        match *b {
+            0x00 => counts.null += 1,
+            0x09..=0x0d | 0x20 => counts.whitespace += 1,
+            b if is_printable(b) => counts.printable += 1,
+            b if b < 0x80 => counts.control += 1,
+            _ => counts.high += 1,
+        }
+    }
+    counts
+}
+
+/// Byte class counts of each of `segments` equal segments of `len` bytes
+/// of `r`, with the segment start offsets.
+///
+/// # Arguments
+///
+/// * `r` - Input, positioned at `base`.
+/// * `base` - Offset of the first byte.
+/// * `len` - Number of bytes to read.
+/// * `segments` - Number of segments.
+pub fn distribution_map<R: Read>(
+    r: &mut R,
+    base: u64,
+    len: u64,
+    segments: u64,
+) -> Result<Vec<(u64, ClassCounts)>> {
+    let size = len.div_ceil(segments.max(1)).max(1);
+    let mut map = Vec::new();
+    let mut segment = Vec::new();
+    let mut at = 0;
+    while at < len {
+        segment.clear();
+        r.take(size.min(len - at)).read_to_end(&mut segment)?;
+        if segment.is_empty() {
+            break;
+        }
+        map.push((base + at, class_counts(&segment)));
+        at += segment.len() as u64;
+    }
+    Ok(map)
+}
+
+/// Color of a byte class in the distribution map.
+///
+/// # Arguments
+///
+/// * `class` - Class name, as given by `ClassCounts::dominant`.
+pub fn class_color(class: &str) -> ansi_term::Color {
+    match class {
+        "printable" => ansi_term::Color::Green,
+        "whitespace" => ansi_term::Color::Cyan,
+        "control" => ansi_term::Color::Yellow,
+        "high" => ansi_term::Color::Red,
+        _ => ansi_term::Color::Fixed(240),
+    }
+}
+
+/// Print the byte class counts under the page summary.
+///
+/// # Arguments
+///
+/// * `counts` - Byte class counts.
+/// * `w` - Output writer.
+pub fn print_class_summary<W: Write>(counts: &ClassCounts, w: &mut W) -> Result<()> {
+    writeln!(
+        w,
+        "   printable: {}, whitespace: {}, control: {}, high: {}, null: {}",
+        counts.printable, counts.whitespace, counts.control, counts.high, counts.null
+    )?;
+    Ok(())
+}
+
+/// Print page bytes as a rust (`r`), c (`c`) or golang (`g`) array. With a
+/// baseline, elements differing from it are highlighted.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `array_format` - r, c or g.
+/// * `baseline` - Earlier content of the input, if highlighting changes.
+/// * `rust_const` - Declare a rust array as a `const` left alone by rustfmt.
+/// * `upper` - Upper case hex digits.
+/// * `w` - Output writer.
+pub fn print_array<W: Write>(
+    page: &Page,
+    array_format: &str,
+    baseline: Option<&[u8]>,
+    rust_const: bool,
+    upper: bool,
+    w: &mut W,
+) -> Result<()> {
+    match array_format {
+        "r" if rust_const => {
+            writeln!(w, "#[rustfmt::skip]\nconst ARRAY: [u8; {}] = [", page.bytes)?
+        }
+        "r" => writeln!(w, "let ARRAY: [u8; {}] = [", page.bytes)?,
+        "c" => writeln!(w, "unsigned char ARRAY[{}] = {{", page.bytes)?,
+        "g" => writeln!(w, "a := [{}]byte{{", page.bytes)?,
+        _ => writeln!(w, "unknown array format")?,
+    }
+
+    let mut i: u64 = 0x0;
+    for line in page.body.iter() {
+        write!(w, "    ")?;
+        for (j, hex) in line.hex_body.iter().enumerate() {
+            i += 1;
+            let at = (line.offset + j as u64) as usize;
+            let element = match baseline {
+                Some(baseline) if baseline.get(at) != Some(hex) => change_style()
+                    .paint(hex_octet(*hex, upper, true))
+                    .to_string(),
+                _ => hex_octet(*hex, upper, true),
+            };
+            if i == page.bytes && array_format != "g" {
+                write!(w, "{}", element)?;
+            } else {
+                write!(w, "{}, ", element)?;
+            }
+        }
+        writeln!(w)?;
+    }
+    match array_format {
+        "r" => writeln!(w, "];")?,
+        "c" => writeln!(w, "}};")?,
+        "g" => writeln!(w, "}}")?,
+        _ => writeln!(w, "unknown array format")?,
+    }
+    Ok(())
+}
+
+/// Print page lines as bare hex octets, without offsets or gutter.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `sep` - Separator between two octets.
+/// * `upper` - Upper case hex digits.
+/// * `w` - Output writer.
+pub fn print_plain<W: Write>(page: &Page, sep: &str, upper: bool, w: &mut W) -> Result<()> {
+    for line in page.body.iter() {
+        let octets: Vec<String> = line
+            .hex_body
+            .iter()
+            .map(|b| hex_octet(*b, upper, false))
+            .collect();
+        writeln!(w, "{}", octets.join(sep))?;
+    }
+    Ok(())
+}
+
+/// Print all page bytes as hex octets on a single line.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `sep` - Separator between two octets.
+/// * `upper` - Upper case hex digits.
+/// * `w` - Output writer.
+pub fn print_oneline<W: Write>(page: &Page, sep: &str, upper: bool, w: &mut W) -> Result<()> {
+    let octets: Vec<String> = page
+        .to_bytes()
+        .iter()
+        .map(|b| hex_octet(*b, upper, false))
+        .collect();
+    writeln!(w, "{}", octets.join(sep))?;
+    Ok(())
+}
+
+/// Escape a string for a json string literal.
+pub fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Print a line as a standalone json object, newline delimited.
+///
+/// # Arguments
+///
+/// * `line` - Line to print.
+/// * `w` - Output writer.
+pub fn print_jsonl_line<W: Write>(line: &Line, w: &mut W) -> Result<()> {
+    let bytes: Vec<String> = line.hex_body.iter().map(|b| b.to_string()).collect();
+    writeln!(
+        w,
+        "{{\"offset\":{},\"bytes\":[{}],\"ascii\":\"{}\"}}",
+        line.offset,
+        bytes.join(","),
+        json_escape(&ascii_gutter(&line.hex_body))
+    )?;
+    Ok(())
+}
+
+/// Print page wrapped in a fenced markdown code block, preceded by a caption.
+/// Colors are always disabled.
+///
+/// # Arguments
+///
+/// * `page` - Page to print.
+/// * `options` - Dump options.
+/// * `caption` - Caption, usually the input file name.
+/// * `lang` - Optional language hint for the fence.
+/// * `w` - Output writer.
+pub fn print_markdown<W: Write>(
+    page: &Page,
+    options: &Options,
+    caption: &str,
+    lang: Option<&str>,
+    w: &mut W,
+) -> Result<()> {
+    let mut options = options.clone();
+    options.colorize = false;
+    writeln!(w, "`{}`", caption)?;
+    writeln!(w)?;
+    writeln!(w, "```{}", lang.unwrap_or(""))?;
+    print_page(page, &options, w)?;
+    writeln!(w, "```")?;
+    Ok(())
+}
+
+/// First offset in `len` bytes of `r` whose byte differs from `fill`, with
+/// that byte.
+///
+/// # Arguments
+///
+/// * `r` - Input, positioned at `start`.
+/// * `start` - Offset of the first byte read.
+/// * `len` - Number of bytes to check.
+/// * `fill` - Expected byte value.
+pub fn check_fill<R: BufRead>(
+    r: &mut R,
+    start: u64,
+    len: u64,
+    fill: u8,
+) -> Result<Option<(u64, u8)>> {
+    for (i, b) in r.take(len).bytes().enumerate() {
+        let b = b?;
+        if b != fill {
+            return Ok(Some((start + i as u64, b)));
+        }
+    }
+    Ok(None)
+}
+
+/// First byte in `len` bytes of `r` that is neither printable nor a tab,
+/// line feed or carriage return, with its offset.
+///
+/// # Arguments
+///
+/// * `r` - Input, positioned at `start`.
+/// * `start` - Offset of the first byte read.
+/// * `len` - Number of bytes to check.
+pub fn first_nonprintable<R: BufRead>(
+    r: &mut R,
+    start: u64,
+    len: u64,
+) -> Result<Option<(u64, u8)>> {
+    for (i, b) in r.take(len).bytes().enumerate() {
+        let b = b?;
+        if !is_printable(b) && !b"\t\n\r".contains(&b) {
+            return Ok(Some((start + i as u64, b)));
+        }
+    }
+    Ok(None)
+}
+
+/// Runs of consecutive zero bytes in `len` bytes of `r`, as offset and
+/// length, shorter runs than `min_run` are left out.
+///
+/// # Arguments
+///
+/// * `r` - Input, positioned at `start`.
+/// * `start` - Offset of the first byte read.
+/// * `len` - Number of bytes to scan.
+/// * `min_run` - Minimum run length reported.
+pub fn null_runs<R: BufRead>(
+    r: &mut R,
+    start: u64,
+    len: u64,
+    min_run: u64,
+) -> Result<Vec<(u64, u64)>> {
+    let mut runs: Vec<(u64, u64)> = Vec::new();
+    let mut run: Option<(u64, u64)> = None;
+    for (i, b) in r.take(len).bytes().enumerate() {
+        if b? == 0 {
+            let (run_start, run_len) = run.unwrap_or((start + i as u64, 0));
+            run = Some((run_start, run_len + 1));
+        } else if let Some(ended) = run.take() {
+            runs.push(ended);
+        }
+    }
+    runs.extend(run);
+    runs.retain(|&(_, run_len)| run_len >= min_run.max(1));
+    Ok(runs)
+}
+
+/// Parse an inclusive byte value range `LOW:HIGH`.
+///
+/// # Arguments
+///
+/// * `s` - The range, bounds in decimal or `0x` hex.
+pub fn parse_value_range(s: &str) -> Option<(u8, u8)> {
+    let mut bounds = s.splitn(2, ':');
+    let low = parse_number(bounds.next()?)?;
+    let high = parse_number(bounds.next()?)?;
+    if low > high || high > u64::from(u8::MAX) {
+        return None;
+    }
+    Some((low as u8, high as u8))
+}
+
+/// Write the bytes of `r` whose value is within `low..=high`, the others
+/// are dropped. Returns the number of bytes written.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `len` - Number of bytes to read.
+/// * `low` - Smallest value kept.
+/// * `high` - Largest value kept.
+/// * `w` - Output writer.
+pub fn extract_bytes<R: BufRead, W: Write>(
+    r: &mut R,
+    len: u64,
+    low: u8,
+    high: u8,
+    w: &mut W,
+) -> Result<u64> {
+    let mut written: u64 = 0;
+    for b in r.take(len).bytes() {
+        let b = b?;
+        if low <= b && b <= high {
+            w.write_all(&[b])?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// Parse a ranges file: one `START:END` range per line, as for `--range`.
+/// Blank lines and lines starting with `#` are skipped.
+///
+/// # Arguments
+///
+/// * `text` - Content of the ranges file.
+pub fn parse_ranges_file(text: &str) -> Result<Vec<(u64, Option<u64>)>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            parse_range(line).ok_or_else(|| Error::Application(format!("invalid range '{}'", line)))
+        })
+        .collect()
+}
+
+/// Write the bytes of every range of `r` one after the other, ranges
+/// clamped to the first `input_len` bytes. Returns the number of bytes
+/// written.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `input_len` - Number of bytes of the input that can be carved.
+/// * `ranges` - Ranges `[start, end)`, an open end meaning end of input.
+/// * `w` - Output writer.
+pub fn carve_ranges<R: Read + Seek, W: Write>(
+    r: &mut R,
+    input_len: u64,
+    ranges: &[(u64, Option<u64>)],
+    w: &mut W,
+) -> Result<u64> {
+    let mut written: u64 = 0;
+    for &(start, end) in ranges {
+        let start = start.min(input_len);
+        let end = end.unwrap_or(input_len).min(input_len);
+        r.seek(SeekFrom::Start(start))?;
+        written += io::copy(&mut r.take(end - start), w)?;
+    }
+    Ok(written)
+}
+
+/// Runs of printable utf-16le characters in `len` bytes of `r`, i.e.
+/// printable ascii bytes each followed by a zero byte, with their offsets.
+/// Runs shorter than `min_len` characters are left out.
+///
+/// # Arguments
+///
+/// * `r` - Input, positioned at `start`.
+/// * `start` - Offset of the first byte read.
+/// * `len` - Number of bytes to scan.
+/// * `min_len` - Minimum string length in characters.
+pub fn utf16_strings<R: BufRead>(
+    r: &mut R,
+    start: u64,
+    len: u64,
+    min_len: usize,
+) -> Result<Vec<(u64, String)>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    r.take(len).read_to_end(&mut bytes)?;
+
+    let mut strings: Vec<(u64, String)> = Vec::new();
+    let mut run_start: usize = 0;
+    let mut run = String::new();
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        if i + 1 < bytes.len() && is_printable(bytes[i]) && bytes[i + 1] == 0 {
+            if run.is_empty() {
+                run_start = i;
+            }
+            run.push(bytes[i] as char);
+            i += 2;
+        } else {
+            if !run.is_empty() {
+                strings.push((start + run_start as u64, run));
+                run = String::new();
+            }
+            i += 1;
+        }
+    }
+    if !run.is_empty() {
+        strings.push((start + run_start as u64, run));
+    }
+    strings.retain(|(_, string)| string.len() >= min_len.max(1));
+    Ok(strings)
+}
+
+/// Skip a byte order mark at the start of `r`, within its first `len`
+/// bytes. Returns the number of bytes skipped.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `len` - Number of bytes left in the input.
+pub fn strip_bom<R: BufRead>(r: &mut R, len: u64) -> Result<u64> {
+    let head = r.fill_buf()?;
+    let bom_len: u64 = match detect_bom(head) {
+        Some("UTF-8") => 3,
+        Some(_) => 2,
+        None => 0,
+    };
+    let bom_len = bom_len.min(len);
+    r.consume(bom_len as usize);
+    Ok(bom_len)
+}
+
+/// Clipboard text source
+#[cfg(feature = "clipboard")]
+pub trait Clipboard {
+    /// clipboard text content
+    fn text(&mut self) -> Result<String>;
+}
+
+/// System clipboard
+#[cfg(feature = "clipboard")]
+#[derive(Copy, Clone, Debug)]
+pub struct SystemClipboard;
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for SystemClipboard {
+    fn text(&mut self) -> Result<String> {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|e| Error::Application(format!("clipboard: {}", e)))
+    }
+}
+
+/// Clipboard bytes: the decoded hex if the content is hex text, the raw
+/// text bytes otherwise.
+///
+/// # Arguments
+///
+/// * `clipboard` - Clipboard to read.
+#[cfg(feature = "clipboard")]
+pub fn clipboard_bytes<C: Clipboard>(clipboard: &mut C) -> Result<Vec<u8>> {
+    let text = clipboard.text()?;
+    Ok(parse_hex_input(&text).unwrap_or_else(|| text.into_bytes()))
+}
+
+/// Resume state of an interrupted dump.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct State {
+    /// number of bytes completely dumped past the start offset
+    pub offset: u64,
+    /// hash of the first KB of input, detects a changed file
+    pub hash: u64,
+}
+
+/// State implementation
+impl State {
+    /// Load state from file, `None` if missing or malformed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - State file path.
+    pub fn load(path: &Path) -> Option<State> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut offset = None;
+        let mut hash = None;
+        for line in content.lines() {
+            let mut kv = line.splitn(2, ' ');
+            match (kv.next(), kv.next()) {
+                (Some("offset"), Some(v)) => offset = v.parse::<u64>().ok(),
+                (Some("hash"), Some(v)) => hash = u64::from_str_radix(v, 16).ok(),
+                _ => {}
+            }
+        }
+        Some(State {
+            offset: offset?,
+            hash: hash?,
+        })
+    }
+
+    /// Save state to file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - State file path.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(
+            path,
+            format!("offset {}\nhash {:016x}\n", self.offset, self.hash),
+        )?;
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a hash.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// CRC-32 (IEEE 802.3) checksum.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, b| {
+        (0..8).fold(crc ^ u32::from(*b), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// CRC-32 of each `block_size` block in `len` bytes of `r`, with the block
+/// offset. The last block may be shorter.
+///
+/// # Arguments
+///
+/// * `r` - Input, positioned at `start`.
+/// * `start` - Offset of the first byte read.
+/// * `len` - Number of bytes to hash.
+/// * `block_size` - Block size in bytes.
+pub fn block_hashes<R: BufRead>(
+    r: &mut R,
+    start: u64,
+    len: u64,
+    block_size: u64,
+) -> Result<Vec<(u64, u32)>> {
+    let mut hashes: Vec<(u64, u32)> = Vec::new();
+    let mut block: Vec<u8> = Vec::new();
+    let mut r = r.take(len);
+    let mut at = start;
+    loop {
+        block.clear();
+        r.by_ref().take(block_size).read_to_end(&mut block)?;
+        if block.is_empty() {
+            return Ok(hashes);
+        }
+        hashes.push((at, crc32(&block)));
+        at += block.len() as u64;
+    }
+}
+
+/// Summaries of each byte position across the `record_size` records in `len`
+/// bytes of `r`, a trailing partial record included.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `len` - Number of bytes to read.
+/// * `record_size` - Record size in bytes.
+pub fn column_summaries<R: BufRead>(
+    r: &mut R,
+    len: u64,
+    record_size: u64,
+) -> Result<Vec<ColumnSummary>> {
+    // records longer than the input only have as many columns as bytes
+    let mut columns: Vec<Vec<u8>> = vec![Vec::new(); record_size.min(len) as usize];
+    for (i, b) in r.take(len).bytes().enumerate() {
+        columns[i % record_size as usize].push(b?);
+    }
+    Ok(columns
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| !column.is_empty())
+        .map(|(position, column)| {
+            let mut seen = [false; 256];
+            for b in column {
+                seen[*b as usize] = true;
+            }
+            ColumnSummary {
+                position: position as u64,
+                hash: crc32(column),
+                distinct: seen.iter().filter(|seen| **seen).count(),
+                first: column[0],
+            }
+        })
+        .collect())
+}
+
+/// Number of bytes equal at identical offsets of two inputs, and the length
+/// of the longer one: the tail of the longer input counts as differing.
+///
+/// # Arguments
+///
+/// * `a` - First input.
+/// * `b` - Second input.
+pub fn similarity<R: BufRead, S: BufRead>(a: R, b: S) -> Result<(u64, u64)> {
+    let (mut a, mut b) = (a.bytes(), b.bytes());
+    let (mut same, mut total) = (0, 0);
+    loop {
+        match (a.next().transpose()?, b.next().transpose()?) {
+            (None, None) => return Ok((same, total)),
+            (x, y) => {
+                total += 1;
+                if x == y {
+                    same += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Smallest period `p` such that at least `threshold` of the bytes equal the
+/// byte `p` positions before them, with that share. Periods go up to half
+/// the input and at most `max_period`.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to analyze.
+/// * `max_period` - Longest period tried.
+/// * `threshold` - Share of repeating bytes, between 0 and 1.
+pub fn detect_period(bytes: &[u8], max_period: usize, threshold: f64) -> Option<(usize, f64)> {
+    (1..=max_period.min(bytes.len() / 2)).find_map(|period| {
+        let repeats = bytes
+            .iter()
+            .zip(bytes[period..].iter())
+            .filter(|(a, b)| a == b)
+            .count();
+        let share = repeats as f64 / (bytes.len() - period) as f64;
+        if share >= threshold {
+            Some((period, share))
+        } else {
+            None
+        }
+    })
+}
+
+/// Low byte of the sum of the fields of a firmware record, the base of the
+/// Intel HEX and S-record checksums.
+///
+/// # Arguments
+///
+/// * `bytes` - Record fields.
+fn record_sum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+/// Upper hexadecimal digits of record fields.
+///
+/// # Arguments
+///
+/// * `bytes` - Record fields.
+fn record_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// One Intel HEX record, `:LLAAAATT` then the data and the checksum.
+///
+/// # Arguments
+///
+/// * `address` - Low 16 bits of the load address.
+/// * `kind` - Record type.
+/// * `data` - Record data.
+pub fn intel_hex_record(address: u16, kind: u8, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8, (address >> 8) as u8, address as u8, kind];
+    bytes.extend_from_slice(data);
+    bytes.push(record_sum(&bytes).wrapping_neg());
+    format!(":{}", record_hex(&bytes))
+}
+
+/// One Motorola S-record, `Sn` then the count, the address, the data and the
+/// checksum.
+///
+/// # Arguments
+///
+/// * `kind` - Record type, 0 to 9.
+/// * `address` - Address field.
+/// * `address_len` - Address field width in bytes, 2 to 4.
+/// * `data` - Record data.
+pub fn srec_record(kind: u8, address: u32, address_len: usize, data: &[u8]) -> String {
+    let mut bytes = vec![(address_len + data.len() + 1) as u8];
+    bytes.extend_from_slice(&address.to_be_bytes()[4 - address_len..]);
+    bytes.extend_from_slice(data);
+    bytes.push(!record_sum(&bytes));
+    format!("S{}{}", kind, record_hex(&bytes))
+}
+
+/// Write `len` bytes of `r`, loaded at `base`, as Motorola S-records: a
+/// header, data records of up to 16 bytes, the record count and the
+/// termination record. Addresses are `address_len` bytes wide, S1 to S3.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `base` - Load address of the first byte.
+/// * `len` - Number of bytes to write.
+/// * `address_len` - Address width in bytes, 2 to 4.
+/// * `header` - Header text, e.g. the input name.
+/// * `w` - Output writer.
+pub fn write_srec<R: Read, W: Write>(
+    r: &mut R,
+    base: u64,
+    len: u64,
+    address_len: usize,
+    header: &str,
+    w: &mut W,
+) -> Result<()> {
+    if base + len > 1 << (8 * address_len) {
+        return Err(Error::Application(format!(
+            "{}-bit s-record addresses cannot reach {}",
+            8 * address_len,
+            offset(base + len)
+        )));
+    }
+    let kind = address_len as u8 - 1;
+    // the count byte covers the address, the header and the checksum
+    let header = &header.as_bytes()[..header.len().min(0xff - 3)];
+    writeln!(w, "{}", srec_record(0, 0, 2, header))?;
+    let mut r = r.take(len);
+    let mut address = base;
+    let mut count: u32 = 0;
+    loop {
+        let mut data = Vec::new();
+        r.by_ref().take(16).read_to_end(&mut data)?;
+        if data.is_empty() {
+            break;
+        }
+        writeln!(
+            w,
+            "{}",
+            srec_record(kind, address as u32, address_len, &data)
+        )?;
+        address += data.len() as u64;
+        count += 1;
+    }
+    if count < 0x10000 {
+        writeln!(w, "{}", srec_record(5, count, 2, &[]))?;
+    } else {
+        writeln!(w, "{}", srec_record(6, count.min(0xff_ffff), 3, &[]))?;
+    }
+    writeln!(w, "{}", srec_record(10 - kind, 0, address_len, &[]))?;
+    Ok(())
+}
+
+/// Number of occurrences of every byte value within `len` bytes of `r`.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `len` - Number of bytes to count.
+pub fn histogram<R: Read>(r: &mut R, len: u64) -> Result<[u64; 256]> {
+    let mut counts = [0u64; 256];
+    let mut chunk = [0u8; 64 * 1024];
+    let mut r = r.take(len);
+    loop {
+        let n = r.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        for b in &chunk[..n] {
+            counts[*b as usize] += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// Byte values present in a histogram with their counts, ordered by value,
+/// or by descending frequency with ties ordered by value.
+///
+/// # Arguments
+///
+/// * `counts` - Occurrences of every byte value.
+/// * `by_freq` - Order by descending frequency instead of by value.
+pub fn histogram_order(counts: &[u64; 256], by_freq: bool) -> Vec<(u8, u64)> {
+    let mut order: Vec<(u8, u64)> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(b, count)| (b as u8, *count))
+        .collect();
+    if by_freq {
+        order.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    }
+    order
+}
+
+/// Byte-wise XOR of two inputs, the shorter one extended with zeros.
+///
+/// # Arguments
+///
+/// * `a` - First input.
+/// * `b` - Second input.
+pub fn xor_bytes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0) ^ b.get(i).unwrap_or(&0))
+        .collect()
+}
+
+/// Escape `text` for html content and attribute values.
+///
+/// # Arguments
+///
+/// * `text` - Text to escape.
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Tooltip of a byte: its decimal, octal and binary values, and its character
+/// when printable, e.g. `dec 65, oct 101, bin 01000001, char 'A'`.
+///
+/// # Arguments
+///
+/// * `b` - Byte to describe.
+pub fn byte_tooltip(b: u8) -> String {
+    let mut tooltip = format!("dec {}, oct {:o}, bin {:08b}", b, b, b);
+    if b.is_ascii_graphic() || b == b' ' {
+        tooltip.push_str(&format!(", char '{}'", b as char));
+    }
+    tooltip
+}
+
+/// Write a page as an html table: one row per line with its offset, hex and
+/// ascii cells, optionally with a tooltip on every hex cell.
+///
+/// # Arguments
+///
+/// * `page` - Page to write.
+/// * `tooltips` - Add a `title` attribute to each hex cell.
+/// * `w` - Output writer.
+pub fn write_html<W: Write>(page: &Page, tooltips: bool, w: &mut W) -> Result<()> {
+    writeln!(w, "<table class=\"hex\">")?;
+    for line in &page.body {
+        write!(w, "<tr><th>{}</th>", offset(line.offset))?;
+        for b in &line.hex_body {
+            if tooltips {
+                write!(
+                    w,
+                    "<td title=\"{}\">{:02x}</td>",
+                    html_escape(&byte_tooltip(*b)),
+                    b
+                )?;
+            } else {
+                write!(w, "<td>{:02x}</td>", b)?;
+            }
+        }
+        let ascii = ascii_gutter(&line.hex_body);
+        writeln!(w, "<td class=\"ascii\">{}</td></tr>", html_escape(&ascii))?;
+    }
+    writeln!(w, "</table>")?;
+    Ok(())
+}
+
+/// Write `len` bytes of `r`, loaded at `base`, as Intel HEX: data records of
+/// up to 16 bytes, an extended linear address record whenever the upper 16
+/// address bits change, and the end of file record.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `base` - Load address of the first byte.
+/// * `len` - Number of bytes to write.
+/// * `w` - Output writer.
+pub fn write_intel_hex<R: Read, W: Write>(r: &mut R, base: u64, len: u64, w: &mut W) -> Result<()> {
+    if base + len > 1 << 32 {
+        return Err(Error::Application(
+            "intel hex addresses are limited to 4GB".to_string(),
+        ));
+    }
+    let mut r = r.take(len);
+    let mut address = base;
+    let mut upper: Option<u64> = if base >> 16 == 0 { Some(0) } else { None };
+    loop {
+        // records never cross a 64KB segment
+        let room = 16.min(0x10000 - (address & 0xffff)) as usize;
+        let mut data = vec![0u8; room];
+        let mut n = 0;
+        while n < room {
+            match r.read(&mut data[n..])? {
+                0 => break,
+                read => n += read,
+            }
+        }
+        if n == 0 {
+            break;
+        }
+        if upper != Some(address >> 16) {
+            let segment = (address >> 16) as u16;
+            writeln!(w, "{}", intel_hex_record(0, 0x04, &segment.to_be_bytes()))?;
+            upper = Some(address >> 16);
+        }
+        writeln!(w, "{}", intel_hex_record(address as u16, 0x00, &data[..n]))?;
+        address += n as u64;
+    }
+    writeln!(w, "{}", intel_hex_record(0, 0x01, &[]))?;
+    Ok(())
+}
+
+/// Lines dumped with `--state` between two saves of the state file
+pub const STATE_SAVE_LINES: u64 = 64;
+
+/// Where and how often a resumable dump records its progress
+#[derive(Copy, Clone, Debug)]
+pub struct Progress<'a> {
+    /// state file path
+    pub path: &'a Path,
+    /// continue from the saved state if it matches the input
+    pub resume: bool,
+    /// lines dumped between two saves of the state, flushing the output
+    pub save_every: u64,
+}
+
+/// Dump streaming line by line, recording progress in a state file every
+/// few lines, so that an interrupted dump can be resumed where it stopped.
+/// Lines are laid out as in a regular dump of `buf_len` bytes from `skip`,
+/// repeated `repeat` times.
+///
+/// # Arguments
+///
+/// * `buf` - Input.
+/// * `skip` - Offset of the first dumped byte.
+/// * `buf_len` - Number of bytes dumped from `skip`.
+/// * `repeat` - Number of repetitions, at least 1.
+/// * `options` - Dump options.
+/// * `progress` - State file settings.
+/// * `w` - Output writer.
+pub fn dump_resumable<R: BufRead + Seek, W: Write>(
+    buf: &mut R,
+    skip: u64,
+    buf_len: u64,
+    repeat: u64,
+    options: &Options,
+    progress: Progress,
+    w: &mut W,
+) -> Result<()> {
+    let mut head: Vec<u8> = Vec::new();
+    buf.seek(SeekFrom::Start(0))?;
+    buf.by_ref().take(1024).read_to_end(&mut head)?;
+    let hash = fnv1a(&head);
+    let total = buf_len.saturating_mul(repeat.max(1));
+
+    let mut done: u64 = 0x0;
+    if progress.resume {
+        match State::load(progress.path) {
+            Some(state) if state.hash == hash => done = state.offset.min(total),
+            Some(_) => eprintln!("state does not match input, dumping from start"),
+            None => {}
+        }
+    }
+    State { offset: done, hash }.save(progress.path)?;
+
+    let columns = options.column_width.max(1);
+    let mut within = if buf_len > 0 { done % buf_len } else { 0 };
+    buf.seek(SeekFrom::Start(skip + within))?;
+    let mut dumped: u64 = 0x0;
+    let mut row: u64 = 0;
+    while done < total {
+        let at = skip + done;
+        let len = match options.record_size {
+            Some(record_size) => {
+                let in_record = at % record_size;
+                (record_size - in_record).min(columns - in_record % columns)
+            }
+            None => columns - done % columns,
+        }
+        .min(total - done);
+        let mut line = Line::new();
+        line.offset = at;
+        line.bytes = len;
+        while (line.hex_body.len() as u64) < len {
+            if within == buf_len {
+                within = 0;
+                buf.seek(SeekFrom::Start(skip))?;
+            }
+            let n = (len - line.hex_body.len() as u64).min(buf_len - within);
+            let filled = line.hex_body.len();
+            line.hex_body.resize(filled + n as usize, 0);
+            buf.read_exact(&mut line.hex_body[filled..])?;
+            within += n;
+        }
+        print_line(&line, row as usize, options, w)?;
+        row += 1;
+        done += len;
+        dumped += len;
+        if row.is_multiple_of(progress.save_every.max(1)) {
+            w.flush()?;
+            State { offset: done, hash }.save(progress.path)?;
+        }
+    }
+    w.flush()?;
+    State { offset: done, hash }.save(progress.path)?;
+    if !options.no_summary {
+        writeln!(w, "   bytes: {}", dumped)?;
+    }
+    Ok(())
+}
+
+/// Terminal width in characters, from the terminal or else `$COLUMNS`.
+pub fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse::<usize>().ok())
+}
+
+/// Largest column width whose lines fit in `width` characters, at least 1.
+///
+/// # Arguments
+///
+/// * `width` - Available width in characters.
+/// * `options` - Output options, the column width aside.
+pub fn fit_columns(width: usize, options: &Options) -> u64 {
+    let label = format_offset(0, options.offset_format, !options.compact).len() + 2;
+    let delims = if options.gutter_delim.is_some() { 2 } else { 0 };
+    // every column takes a byte, its separator and a gutter character
+    let column = format_byte(0, options.format, !options.compact).len() + 2;
+    (width.saturating_sub(label + delims) / column).max(1) as u64
+}
+
+/// Dump the file, then keep polling it and dump appended bytes as they
+/// arrive, like `tail -f`. Offsets continue from where the previous chunk
+/// stopped; if the file shrinks, it is treated as truncated and followed
+/// again from offset 0.
+///
+/// # Arguments
+///
+/// * `path` - File to follow.
+/// * `start` - Offset of the first byte to dump.
+/// * `options` - Output options.
+/// * `interval` - Delay between two polls.
+/// * `deadline` - Stop polling after this instant, `None` follows forever.
+/// * `w` - Output writer.
+pub fn follow<W: Write>(
+    path: &Path,
+    start: u64,
+    options: &Options,
+    interval: Duration,
+    deadline: Option<Instant>,
+    w: &mut W,
+) -> Result<()> {
+    let mut options = options.clone();
+    let mut position = start;
+    let mut row: usize = 0;
+    loop {
+        if options.auto_columns {
+            if let Some(width) = terminal_width() {
+                options.column_width = fit_columns(width, &options);
+            }
+        }
+        let len = fs::metadata(path)?.len();
+        if len < position {
+            eprintln!("{}: file truncated", path.display());
+            position = 0;
+        }
+        if len > position {
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(position))?;
+            let mut buf = BufReader::new(file);
+            let page = buf_to_page(&mut buf, position, len - position, options.column_width)?;
+            for line in &page.body {
+                print_line(line, row, &options, w)?;
+                row += 1;
+            }
+            w.flush()?;
+            position += page.bytes;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(());
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Clear the terminal and move the cursor home.
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Render, then keep polling the file and render again after clearing the
+/// screen whenever its modification time or length changes, or the
+/// terminal is resized.
+///
+/// # Arguments
+///
+/// * `path` - File to watch.
+/// * `interval` - Delay between two polls.
+/// * `deadline` - Stop polling after this instant, `None` watches forever.
+/// * `w` - Output writer.
+/// * `width` - Terminal width, `None` if not relevant to the rendering.
+/// * `render` - Dump of the file.
+pub fn watch<W, S, F>(
+    path: &Path,
+    interval: Duration,
+    deadline: Option<Instant>,
+    w: &mut W,
+    mut width: S,
+    mut render: F,
+) -> Result<()>
+where
+    W: Write,
+    S: FnMut() -> Option<usize>,
+    F: FnMut(&mut W) -> Result<()>,
+{
+    let mut version = |path: &Path| -> Result<_> {
+        let metadata = fs::metadata(path)?;
+        Ok((metadata.modified()?, metadata.len(), width()))
+    };
+    let mut seen = version(path)?;
+    render(w)?;
+    w.flush()?;
+    loop {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(());
+        }
+        thread::sleep(interval);
+        let current = version(path)?;
+        if current != seen {
+            seen = current;
+            write!(w, "{}", CLEAR_SCREEN)?;
+            render(w)?;
+            w.flush()?;
+        }
+    }
+}
+
+/// Connect to a remote receiver of the dump.
+///
+/// # Arguments
+///
+/// * `addr` - Address of the receiver, as `HOST:PORT`.
+pub fn connect(addr: &str) -> Result<TcpStream> {
+    TcpStream::connect(addr)
+        .map_err(|e| Error::Application(format!("cannot connect to '{}': {}", addr, e)))
+}
+
+/// Input wrapper counting the bytes read through it
+#[derive(Debug)]
+pub struct Counter<R> {
+    inner: R,
+    /// bytes read so far
+    pub count: u64,
+}
+
+/// Counter implementation
+impl<R> Counter<R> {
+    /// Counter constructor
+    pub fn new(inner: R) -> Counter<R> {
+        Counter { inner, count: 0 }
+    }
+}
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Counter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.inner.consume(amt)
+    }
+}
+
+impl<R: Seek> Seek for Counter<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// Input wrapper reporting every `every` bytes the offset reached, once per
+/// offset even when the input is read again after a seek
+#[derive(Debug)]
+pub struct Checkpoint<R, W> {
+    inner: R,
+    every: Option<u64>,
+    report: W,
+    position: u64,
+    reported: u64,
+}
+
+/// Checkpoint implementation
+impl<R, W: Write> Checkpoint<R, W> {
+    /// Checkpoint constructor, `every` set to `None` reports nothing
+    pub fn new(inner: R, every: Option<u64>, report: W) -> Checkpoint<R, W> {
+        Checkpoint {
+            inner,
+            every: every.filter(|every| *every > 0),
+            report,
+            position: 0,
+            reported: 0,
+        }
+    }
+
+    fn advance(&mut self, n: usize) -> io::Result<()> {
+        self.position += n as u64;
+        if let Some(every) = self.every {
+            while self.reported + every <= self.position {
+                self.reported += every;
+                writeln!(self.report, "checkpoint: {}", offset(self.reported))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read, W: Write> Read for Checkpoint<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.advance(n)?;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, W: Write> BufRead for Checkpoint<R, W> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        // progress reports are best effort
+        let _ = self.advance(amt);
+    }
+}
+
+impl<R: Seek, W: Write> Seek for Checkpoint<R, W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(pos)?;
+        if let Some(every) = self.every {
+            self.reported = self.reported.max(self.position - self.position % every);
+        }
+        Ok(self.position)
+    }
+}
+
+/// Output wrapper starting every line written through it with a prefix
+#[derive(Debug)]
+pub struct Prefixed<W> {
+    inner: W,
+    prefix: String,
+    line_start: bool,
+}
+
+/// Prefixed implementation
+impl<W> Prefixed<W> {
+    /// Prefixed constructor
+    pub fn new(inner: W, prefix: &str) -> Prefixed<W> {
+        Prefixed {
+            inner,
+            prefix: prefix.to_string(),
+            line_start: true,
+        }
+    }
+}
+
+impl<W: Write> Write for Prefixed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in buf.split_inclusive(|b| *b == b'\n') {
+            if self.line_start {
+                self.inner.write_all(self.prefix.as_bytes())?;
+            }
+            self.inner.write_all(line)?;
+            self.line_start = line.ends_with(b"\n");
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Output wrapper able to hold back the newline ending the output, written
+/// only once more output follows it
+#[derive(Debug)]
+pub struct FinalNewline<W> {
+    inner: W,
+    omit: bool,
+    pending: bool,
+}
+
+/// FinalNewline implementation
+impl<W> FinalNewline<W> {
+    /// FinalNewline constructor, `omit` drops the final newline
+    pub fn new(inner: W, omit: bool) -> FinalNewline<W> {
+        FinalNewline {
+            inner,
+            omit,
+            pending: false,
+        }
+    }
+}
+
+impl<W: Write> Write for FinalNewline<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.omit || buf.is_empty() {
+            return self.inner.write(buf);
+        }
+        if self.pending {
+            self.inner.write_all(b"\n")?;
+        }
+        self.pending = buf.ends_with(b"\n");
+        let end = buf.len() - self.pending as usize;
+        self.inner.write_all(&buf[..end])?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Print timing and throughput of a dump.
+///
+/// # Arguments
+///
+/// * `bytes` - Number of bytes read.
+/// * `elapsed` - Duration of the read and dump.
+/// * `w` - Output writer, stderr to keep the dump clean.
+pub fn print_stats<W: Write>(bytes: u64, elapsed: Duration, w: &mut W) -> Result<()> {
+    let secs = elapsed.as_secs_f64();
+    let throughput = if secs > 0.0 {
+        bytes as f64 / secs / 1e6
+    } else {
+        f64::INFINITY
+    };
+    writeln!(
+        w,
+        "stats: {} bytes in {:.6} s, {:.2} MB/s",
+        bytes, secs, throughput
+    )?;
+    Ok(())
+}
+
+/// Compression of an input, from its file extension or else its magic.
+///
+/// # Arguments
+///
+/// * `path` - Input path.
+/// * `head` - First bytes of the input.
+pub fn detect_compression(path: &Path, head: &[u8]) -> Option<Compression> {
+    let by_extension = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gz") => Some(Compression::Gzip),
+        Some("zst") => Some(Compression::Zstd),
+        Some("bz2") => Some(Compression::Bzip2),
+        Some("xz") => Some(Compression::Xz),
+        _ => None,
+    };
+    by_extension.or(if head.starts_with(&[0x1f, 0x8b]) {
+        Some(Compression::Gzip)
+    } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some(Compression::Zstd)
+    } else if head.starts_with(b"BZh") {
+        Some(Compression::Bzip2)
+    } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Some(Compression::Xz)
+    } else {
+        None
+    })
+}
+
+/// Decompress a whole input, at most `MAX_PAGE_LEN` bytes once decompressed.
+///
+/// # Arguments
+///
+/// * `r` - Compressed input.
+/// * `compression` - Its compression, whose decoder must be built in.
+#[allow(unused_mut)]
+#[cfg_attr(
+    not(any(feature = "gzip", feature = "zstd", feature = "bz2", feature = "xz")),
+    allow(unused_variables, unreachable_code)
+)]
+pub fn decompress<R: BufRead>(mut r: R, compression: Compression) -> Result<Vec<u8>> {
+    let mut decoder: Box<dyn Read> = match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(r)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(
+            ruzstd::StreamingDecoder::new(r)
+                .map_err(|e| Error::Application(format!("zstd: {}", e)))?,
+        ),
+        #[cfg(feature = "bz2")]
+        Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(r)),
+        #[cfg(feature = "xz")]
+        Compression::Xz => {
+            let mut bytes: Vec<u8> = Vec::new();
+            lzma_rs::xz_decompress(&mut r, &mut bytes)
+                .map_err(|e| Error::Application(format!("xz: {}", e)))?;
+            Box::new(io::Cursor::new(bytes))
+        }
+        #[allow(unreachable_patterns)]
+        _ => {
+            return Err(Error::Application(format!(
+                "{:?} input needs the matching decompression feature",
+                compression
+            )))
+        }
+    };
+    let mut bytes: Vec<u8> = Vec::new();
+    decoder
+        .by_ref()
+        .take(MAX_PAGE_LEN + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_PAGE_LEN {
+        return Err(Error::Application(format!(
+            "decompressed input exceeds the {}MB in-memory limit",
+            MAX_PAGE_LEN >> 20
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Read a non-seekable input such as stdin into memory. With `total_size`,
+/// exactly that many bytes are read and a shorter input is an error, without
+/// it an input over `MAX_PAGE_LEN` bytes is.
+///
+/// # Arguments
+///
+/// * `r` - Input.
+/// * `total_size` - Expected input length, known from elsewhere.
+pub fn read_stream<R: Read>(r: R, total_size: Option<u64>) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match total_size {
+        Some(total) => {
+            r.take(total).read_to_end(&mut bytes)?;
+            if (bytes.len() as u64) < total {
+                return Err(Error::Mismatch(format!(
+                    "input ended after {} of {} bytes",
+                    bytes.len(),
+                    total
+                )));
+            }
+        }
+        None => {
+            r.take(MAX_PAGE_LEN + 1).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 > MAX_PAGE_LEN {
+                return Err(Error::Application(format!(
+                    "input exceeds the {}MB in-memory limit",
+                    MAX_PAGE_LEN >> 20
+                )));
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Standard output of a shell command, failing if the command does not exit
+/// successfully. Its standard error goes to ours. A command stalling for
+/// longer than `timeout` is killed.
+///
+/// # Arguments
+///
+/// * `command` - Command line, run by `sh -c`, or `cmd /C` on windows.
+/// * `timeout` - Longest wait for the next chunk of output, if any.
+pub fn command_output(command: &str, timeout: Option<Duration>) -> Result<Vec<u8>> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = std::process::Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::inherit())
+        .spawn()?;
+    let mut stdout = child.stdout.take().expect("piped standard output");
+    let output = match timeout {
+        Some(timeout) => read_with_timeout(stdout, timeout),
+        None => {
+            let mut bytes = Vec::new();
+            stdout
+                .read_to_end(&mut bytes)
+                .map(|_| bytes)
+                .map_err(Error::Io)
+        }
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    };
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(Error::Application(format!(
+            "command '{}' failed: {}",
+            command, status
+        )));
+    }
+    Ok(output)
+}
+
+/// Read a whole input, failing if no data arrives within `timeout`. The
+/// reads happen on a watchdog thread, which a stalled input leaves blocked.
+///
+/// # Arguments
+///
+/// * `r` - Input, e.g. a pipe or a socket.
+/// * `timeout` - Longest wait for the next chunk of data.
+pub fn read_with_timeout<R: Read + Send + 'static>(mut r: R, timeout: Duration) -> Result<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel::<io::Result<Vec<u8>>>();
+    thread::spawn(move || loop {
+        let mut chunk = vec![0u8; 8192];
+        let read = r.read(&mut chunk).map(|n| {
+            chunk.truncate(n);
+            chunk
+        });
+        let last = read.as_ref().map_or(true, |chunk| chunk.is_empty());
+        if sender.send(read).is_err() || last {
+            return;
+        }
+    });
+
+    let mut bytes: Vec<u8> = Vec::new();
+    loop {
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(chunk)) if chunk.is_empty() => return Ok(bytes),
+            Ok(Ok(chunk)) => {
+                bytes.extend_from_slice(&chunk);
+                if bytes.len() as u64 > MAX_PAGE_LEN {
+                    return Err(Error::Application(format!(
+                        "input exceeds the {}MB in-memory limit",
+                        MAX_PAGE_LEN >> 20
+                    )));
+                }
+            }
+            Ok(Err(e)) => return Err(Error::Io(e)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                return Err(Error::Application(format!(
+                    "read timed out, no data within {:.3} s",
+                    timeout.as_secs_f64()
+                )))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(bytes),
+        }
+    }
+}
+
+/// Load a byte substitution table: a file of exactly 256 bytes, the byte at
+/// index `i` replacing every byte `i` of the input.
+///
+/// # Arguments
+///
+/// * `path` - Table file.
+pub fn load_map_table(path: &str) -> Result<[u8; 256]> {
+    let bytes = fs::read(path)?;
+    let mut table = [0u8; 256];
+    if bytes.len() != table.len() {
+        return Err(Error::Application(format!(
+            "map table '{}' has {} entries, expected 256",
+            path,
+            bytes.len()
+        )));
+    }
+    table.copy_from_slice(&bytes);
+    Ok(table)
+}
+
+/// Substitute every byte through a table.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to remap in place.
+/// * `table` - Substitution table, `None` leaves the bytes as they are.
+pub fn remap(bytes: &mut [u8], table: Option<&[u8; 256]>) {
+    if let Some(table) = table {
+        for b in bytes.iter_mut() {
+            *b = table[*b as usize];
+        }
+    }
+}
+
+/// Command line interface: every argument `run` understands.
+pub fn app<'a, 'b>() -> App<'a, 'b> {
+    let app = App::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(env!("CARGO_PKG_DESCRIPTION")) // CARGO_PKG_HOMEPAGE
+        .author(env!("CARGO_PKG_AUTHORS"))
+        .arg(
+            Arg::with_name("cols")
+                .short("c")
+                .long("cols")
+                .value_name("columns")
+                .help("Set column length, auto to fit the terminal width")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("len")
+                .short("l")
+                .long("len")
+                .value_name("len")
+                .help("Set <len> bytes to read")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .help("Set format of octet: Octal (o), LowerHex (x), UpperHex (X), Pointer (p), Binary (b)")
+                .possible_values(&["o", "x", "X", "p", "b"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("INPUTFILE")
+                .help("Pass file path as an argument for hex dump, - for stdin")
+                .required_unless_one(&["from-clipboard", "cmd"])
+                .index(1),
+        ).arg(
+            Arg::with_name("v")
+                .short("v")
+                .multiple(true)
+                .help("Sets verbosity level"),
+        ).arg(
+            Arg::with_name("color")
+                .short("t")
+                .long("color")
+                .help("Set color tint terminal output. 0 or never to disable, 1 or always to enable, auto for terminals only")
+                .default_value("1")
+                .possible_values(&["0", "1", "never", "auto", "always"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("array")
+                .short("a")
+                .long("array")
+                .value_name("array_format")
+                .help("Set source code format output: rust (r), C (c), golang (g)")
+                .possible_values(&["r", "c", "g"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("func")
+                .short("u")
+                .long("func")
+                .value_name("func_length")
+                .help("Set function wave length")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("places")
+                .short("p")
+                .long("places")
+                .value_name("func_places")
+                .help("Set decimal places of function wave and --fixed output")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("record-size")
+                .long("record-size")
+                .value_name("record_size")
+                .help("Set record size in bytes, records start on a new line")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("record-label")
+                .long("record-label")
+                .value_name("template")
+                .help("Set record header template: {n} record index, {offset} record offset")
+                .requires("record-size")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("auto")
+                .long("auto")
+                .help("Detect text input and switch to a wider utf-8 gutter display"),
+        ).arg(
+            Arg::with_name("markdown")
+                .long("markdown")
+                .help("Wrap the dump in a fenced markdown code block, colors disabled"),
+        ).arg(
+            Arg::with_name("markdown-lang")
+                .long("markdown-lang")
+                .value_name("lang")
+                .help("Set the markdown code block language hint")
+                .requires("markdown")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("state")
+                .long("state")
+                .value_name("path")
+                .help("Record dump progress in a state file")
+                // a resumable dump streams plain lines, without page passes
+                .conflicts_with_all(&[
+                    "array",
+                    "plain",
+                    "oneline",
+                    "jsonl",
+                    "until-ascii",
+                    "mark",
+                    "sort-lines",
+                    "crc-delta",
+                    "trim-trailing",
+                    "rle",
+                    "align",
+                    "split-on",
+                    "tlv",
+                    "struct",
+                    "markdown",
+                    "diff",
+                    "grid",
+                    "print-page",
+                    "class-summary",
+                ])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .help("Resume an interrupted dump from its state file")
+                .requires("state"),
+        ).arg(
+            Arg::with_name("minimap")
+                .long("minimap")
+                .help("Show a minimap of the whole file density next to the dump"),
+        ).arg(
+            Arg::with_name("range")
+                .short("r")
+                .long("range")
+                .value_name("start:end")
+                .help("Set byte range to read, end exclusive, e.g. 0x10:0x80")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("expect-fill")
+                .long("expect-fill")
+                .value_name("byte")
+                .help("Check that every byte read equals <byte>, fail at the first mismatch")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("jsonl")
+                .long("jsonl")
+                .help("Output one json object per line: offset, bytes and ascii"),
+        ).arg(
+            Arg::with_name("gutter-delim")
+                .long("gutter-delim")
+                .value_name("pair")
+                .help("Set ascii gutter delimiters, a pair of characters such as \"||\", or none")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("seek-percent")
+                .long("seek-percent")
+                .value_name("percent")
+                .help("Start the dump at <percent> of the file length")
+                .conflicts_with("range")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("insn-lengths")
+                .long("insn-lengths")
+                .value_name("lengths")
+                .help("Draw separators between instructions of the given lengths, a comma list or a file")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("offset-last")
+                .long("offset-last")
+                .help("Label each line with the offset of its last byte"),
+        ).arg(
+            Arg::with_name("tlv")
+                .long("tlv")
+                .help("Annotate the input as type-length-value records"),
+        ).arg(
+            Arg::with_name("tlv-widths")
+                .long("tlv-widths")
+                .value_name("type:length")
+                .help("Set tlv type and length field widths in bytes, default 1:1")
+                .requires("tlv")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("endian")
+                .long("endian")
+                .value_name("endian")
+                .help("Set byte order of decoded values: big (be), little (le); tlv defaults to big, struct and fixed to little")
+                .possible_values(&["big", "be", "little", "le"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("compact")
+                .long("compact")
+                .help("Tighten the layout: bare offsets, no radix prefix on octets"),
+        ).arg(
+            Arg::with_name("struct")
+                .long("struct")
+                .value_name("layout")
+                .help("Decode and annotate a struct at the dump start, e.g. u32:magic,u16:version,8s:name")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("line-numbers")
+                .long("line-numbers")
+                .help("Prefix each line with a 1-based line number"),
+        ).arg(
+            Arg::with_name("follow")
+                .short("F")
+                .long("follow")
+                .conflicts_with_all(&[
+                    "array",
+                    "jsonl",
+                    "state",
+                    "len",
+                    "range",
+                    "cmd",
+                    "from-clipboard",
+                ])
+                .help("Keep dumping bytes appended to the file, like tail -f"),
+        ).arg(
+            Arg::with_name("send")
+                .long("send")
+                .value_name("HOST:PORT")
+                .help("Send the dump to a TCP receiver instead of stdout")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("codepage")
+                .long("codepage")
+                .value_name("CODEPAGE")
+                .help("Render the ascii gutter with a code page")
+                .possible_values(&["cp437", "latin1", "cp1252"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("null-runs")
+                .long("null-runs")
+                .help("Report runs of zero bytes instead of dumping"),
+        ).arg(
+            Arg::with_name("min-run")
+                .long("min-run")
+                .value_name("LENGTH")
+                .help("Shortest zero run reported by --null-runs")
+                .requires("null-runs")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("baseline")
+                .long("baseline")
+                .value_name("FILE")
+                .help("Mark lines, or array elements, that differ from an earlier copy of the input")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("raw")
+                .long("raw")
+                .help("Write the input bytes as is instead of dumping them"),
+        ).arg(
+            Arg::with_name("extract-range")
+                .long("extract-range")
+                .value_name("LOW:HIGH")
+                .help("Only write bytes whose value is within LOW..=HIGH")
+                .requires("raw")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("Write to FILE instead of stdout")
+                .conflicts_with("send")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .help("Print timing and throughput to stderr"),
+        ).arg(
+            Arg::with_name("offset-format")
+                .long("offset-format")
+                .help("Set format of offsets: hex (x), decimal (d), octal (o)")
+                .possible_values(&["x", "d", "o"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("byte-format")
+                .long("byte-format")
+                .help("Set format of octets, overriding --format")
+                .possible_values(&["o", "x", "X", "p", "b"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("zebra")
+                .long("zebra")
+                .help("Alternate the background of every other line"),
+        ).arg(
+            Arg::with_name("strings-utf16")
+                .long("strings-utf16")
+                .help("Print utf-16le strings with their offsets instead of dumping"),
+        ).arg(
+            Arg::with_name("min-len")
+                .long("min-len")
+                .value_name("LENGTH")
+                .help("Shortest string printed by --strings-utf16, 4 by default")
+                .requires("strings-utf16")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("skip")
+                .short("s")
+                .long("skip")
+                .value_name("OFFSET")
+                .help("Start dumping at OFFSET, decimal or 0x hexadecimal")
+                .conflicts_with_all(&["range", "seek-percent"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("align")
+                .long("align")
+                .value_name("BOUNDARY")
+                .help("Lay lines out on a grid starting at the BOUNDARY below the start offset")
+                .conflicts_with_all(&["record-size", "tlv", "struct"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("no-decompress")
+                .long("no-decompress")
+                .help("Dump compressed input as is instead of decompressing it"),
+        ).arg(
+            Arg::with_name("strip-bom")
+                .long("strip-bom")
+                .help("Drop a leading byte order mark from --raw output")
+                .requires("raw"),
+        ).arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .help("Clear the screen and dump again whenever the file changes")
+                .conflicts_with("follow"),
+        ).arg(
+            Arg::with_name("class-summary")
+                .long("class-summary")
+                .help("Count printable, whitespace, control, high and null bytes in the summary"),
+        ).arg(
+            Arg::with_name("repeat")
+                .long("repeat")
+                .value_name("N")
+                .help("Dump the input N times in sequence, with continuing offsets")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("mark")
+                .long("mark")
+                .value_name("HEX")
+                .help("Highlight every occurrence of the hex bytes, e.g. \"de ad\"")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("accent")
+                .long("accent")
+                .value_name("COLOR")
+                .help("Highlight color: black, red, green, yellow, blue, purple, cyan or white")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .help("Fail if no input data arrives within SECONDS, e.g. on a stalled pipe")
+                .conflicts_with_all(&["follow", "watch"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("block-hash")
+                .long("block-hash")
+                .value_name("SIZE")
+                .help("Print the crc32 of each SIZE-byte block instead of dumping")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("no-trailing-space")
+                .long("no-trailing-space")
+                .help("Never end a line with whitespace"),
+        ).arg(
+            Arg::with_name("require-printable")
+                .long("require-printable")
+                .help("Fail at the first byte that is neither printable nor a tab or line break"),
+        ).arg(
+            Arg::with_name("plain")
+                .long("plain")
+                .help("Print bare hex octets, --cols per line")
+                .conflicts_with_all(&["array", "jsonl", "oneline"]),
+        ).arg(
+            Arg::with_name("oneline")
+                .long("oneline")
+                .help("Print all bytes as bare hex octets on one line")
+                .conflicts_with_all(&["array", "jsonl"]),
+        ).arg(
+            Arg::with_name("sep-str")
+                .long("sep-str")
+                .value_name("SEPARATOR")
+                .help("Separator between octets in --plain (space by default) and --oneline (none by default)")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("until-ascii")
+                .long("until-ascii")
+                .value_name("TEXT")
+                .help("Stop dumping after the line where the ascii text contains TEXT")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("column-hash")
+                .long("column-hash")
+                .value_name("RECORD_SIZE")
+                .help("Summarize each byte position across RECORD_SIZE records instead of dumping")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("uuid-at")
+                .long("uuid-at")
+                .value_name("OFFSET")
+                .help("Annotate the 16 bytes at OFFSET as a uuid, may be repeated")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        ).arg(
+            Arg::with_name("uuid-ms")
+                .long("uuid-ms")
+                .help("Read --uuid-at values in the Microsoft GUID mixed-endian order")
+                .requires("uuid-at"),
+        ).arg(
+            Arg::with_name("hyperlinks")
+                .long("hyperlinks")
+                .help("Make offsets terminal hyperlinks when colorizing"),
+        ).arg(
+            Arg::with_name("hyperlink-template")
+                .long("hyperlink-template")
+                .value_name("TEMPLATE")
+                .help("Hyperlink target, {offset} is replaced by the line offset, default file://INPUTFILE#{offset}")
+                .takes_value(true)
+                .requires("hyperlinks"),
+        ).arg(
+            Arg::with_name("binary-compact")
+                .long("binary-compact")
+                .help("Binary octets without the 0b prefix, one space apart, implies --compact"),
+        ).arg(
+            Arg::with_name("total-size")
+                .long("total-size")
+                .value_name("N")
+                .help("Read exactly N bytes from stdin, failing if it ends early")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("rle")
+                .long("rle")
+                .alias("dedupe-bytes")
+                .help("Annotate runs of identical bytes as 0xNN\u{d7}COUNT while dumping"),
+        ).arg(
+            Arg::with_name("rle-min")
+                .long("rle-min")
+                .value_name("LENGTH")
+                .help("Shortest run annotated by --rle, 4 by default")
+                .requires("rle")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("prefix")
+                .long("prefix")
+                .value_name("TEXT")
+                .help("Start every output line with TEXT, e.g. an indentation")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("fixed")
+                .long("fixed")
+                .value_name("Qm.n")
+                .help("Print the input as signed fixed-point values with m integer and n fractional bits")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("no-eof-newline")
+                .long("no-eof-newline")
+                .alias("no-final-newline")
+                .help("Omit the newline ending the output"),
+        ).arg(
+            Arg::with_name("quote-strings")
+                .long("quote-strings")
+                .help("Quote printable runs in the ascii gutter and escape other bytes"),
+        ).arg(
+            Arg::with_name("addr-stride")
+                .long("addr-stride")
+                .value_name("N")
+                .help("Label offsets as addresses advancing by N per byte")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("similarity")
+                .long("similarity")
+                .alias("compare-summary")
+                .value_name("FILE2")
+                .help("Report the share of bytes equal to FILE2 at the same offsets instead of dumping")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("distribution-map")
+                .long("distribution-map")
+                .help("Print the dominant byte class of each segment of the input instead of dumping"),
+        ).arg(
+            Arg::with_name("segments")
+                .long("segments")
+                .value_name("N")
+                .help("Number of segments of --distribution-map, 16 by default")
+                .requires("distribution-map")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("assert")
+                .long("assert")
+                .value_name("NAME==VALUE")
+                .help("Check a --struct field against a value, or NAME!=VALUE, may be repeated")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .requires("struct"),
+        ).arg(
+            Arg::with_name("ptrs")
+                .long("ptrs")
+                .help("Show each 8-byte group as a little-endian 64-bit pointer, same as --format p"),
+        ).arg(
+            Arg::with_name("modeline")
+                .long("modeline")
+                .help("Apply the cols, format and offset-format settings of a '# hex: cols=16 format=x' first line"),
+        ).arg(
+            Arg::with_name("split-on")
+                .long("split-on")
+                .value_name("BYTE")
+                .help("Split the dump into records ended by BYTE, offsets relative to each record")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("hide-delimiter")
+                .long("hide-delimiter")
+                .help("Leave the delimiter bytes of --split-on out of the dump")
+                .requires("split-on"),
+        ).arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Report the effective settings on stderr and exit without dumping"),
+        ).arg(
+            Arg::with_name("escape-gutter")
+                .long("escape-gutter")
+                .help("Backslash-escape gutter delimiters and backslashes found in the gutter"),
+        ).arg(
+            Arg::with_name("interleave")
+                .long("interleave")
+                .value_name("FILE2")
+                .help("Follow every line with the line of FILE2 at the same offset, labeled A and B")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("print-page")
+                .long("print-page")
+                .alias("max-lines-per-page")
+                .value_name("LINES")
+                .help("Paginate for printing: a header and footer every LINES lines, pages split by form feeds")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("leb128")
+                .long("leb128")
+                .value_name("OFFSET")
+                .help("Annotate the LEB128 integer at OFFSET, may be repeated")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        ).arg(
+            Arg::with_name("der")
+                .long("der")
+                .value_name("OFFSET")
+                .help("Annotate the ASN.1 DER tag and length at OFFSET, may be repeated")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        ).arg(
+            Arg::with_name("alt-group-color")
+                .long("alt-group-color")
+                .help("Tint byte groups alternately light and dark instead of by value"),
+        ).arg(
+            Arg::with_name("group-size")
+                .long("group-size")
+                .value_name("N")
+                .help("Bytes per group of --alt-group-color, 4 by default")
+                .requires("alt-group-color")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("bitmask")
+                .long("bitmask")
+                .alias("mask")
+                .help("List the set bit positions of every byte after the gutter, e.g. [7 4 0]"),
+        ).arg(
+            Arg::with_name("map-table")
+                .long("map-table")
+                .value_name("PATH")
+                .help("Substitute every input byte through a table of 256 bytes read from PATH")
+                .conflicts_with("watch")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("diff")
+                .long("diff")
+                .value_name("FILE2")
+                .help("Print only the lines differing from FILE2, with context lines, in hunks")
+                .conflicts_with("baseline")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("context")
+                .long("context")
+                .value_name("N")
+                .help("Unchanged lines printed around each change of --diff, 3 by default")
+                .requires("diff")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("period")
+                .long("period")
+                .alias("repeat-detect")
+                .help("Report the smallest period with which 90% of the input repeats instead of dumping"),
+        ).arg(
+            Arg::with_name("max-period")
+                .long("max-period")
+                .value_name("N")
+                .help("Longest period tried by --period, 4096 by default")
+                .requires("period")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("stop-at-null")
+                .long("stop-at-null")
+                .alias("truncate-on-null")
+                .help("End the dump before the first zero byte, e.g. with --skip to view a C string"),
+        ).arg(
+            Arg::with_name("intel-hex")
+                .long("intel-hex")
+                .help("Write the input as Intel HEX records, loaded at the dumped offset"),
+        ).arg(
+            Arg::with_name("srec")
+                .long("srec")
+                .help("Write the input as Motorola S-records, loaded at the dumped offset"),
+        ).arg(
+            Arg::with_name("srec-width")
+                .long("srec-width")
+                .value_name("BITS")
+                .help("Address width of --srec: 16, 24 or 32, the narrowest fitting by default")
+                .requires("srec")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .value_name("N")
+                .help("Report the offset reached every N bytes on stderr")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("html")
+                .long("html")
+                .help("Write the dump as an html table"),
+        ).arg(
+            Arg::with_name("tooltips")
+                .long("tooltips")
+                .help("Show the decimal, octal, binary and character values of a byte on hover")
+                .requires("html"),
+        ).arg(
+            Arg::with_name("histogram")
+                .long("histogram")
+                .help("Print the number of occurrences of every byte value instead of dumping"),
+        ).arg(
+            Arg::with_name("histogram-sort")
+                .long("histogram-sort")
+                .help("Order the histogram by byte value or by frequency, freq by default")
+                .possible_values(&["value", "freq"])
+                .requires("histogram")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("ranges-file")
+                .long("ranges-file")
+                .value_name("PATH")
+                .help("Write the bytes of every START:END range listed in PATH one after the other")
+                .requires("raw")
+                .conflicts_with("extract-range")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("no-summary")
+                .long("no-summary")
+                .help("Leave out the byte count closing the dump"),
+        ).arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .help("Apply a bundle of options, explicit options taking precedence")
+                .possible_values(&["diff", "compact", "embed"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("percent")
+                .long("percent")
+                .help("Prefix every line with how far through the input it starts"),
+        ).arg(
+            Arg::with_name("xor-with")
+                .long("xor-with")
+                .value_name("FILE2")
+                .help("Dump the byte-wise XOR of the input and FILE2, the shorter one extended with zeros")
+                .conflicts_with("interleave")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("grid")
+                .long("grid")
+                .help("Frame the dump as a table, with borders around the offset, hex and gutter columns")
+                .conflicts_with_all(&["markdown", "diff", "print-page"]),
+        ).arg(
+            Arg::with_name("const")
+                .long("const")
+                .help("Declare a rust --array as a const, marked #[rustfmt::skip]")
+                .requires("array"),
+        ).arg(
+            Arg::with_name("len-field")
+                .long("len-field")
+                .value_name("OFFSET:WIDTH:ENDIAN")
+                .help("Dump the bytes following a length field of the input, WIDTH bytes big (be) or little (le) endian")
+                .conflicts_with_all(&["len", "range", "skip", "seek-percent"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("trim-trailing")
+                .long("trim-trailing")
+                .help("Collapse the run of identical lines ending the dump, e.g. padding"),
+        ).arg(
+            Arg::with_name("swap-words")
+                .long("swap-words")
+                .value_name("N")
+                .help("Show the bytes of every N-byte group in reverse order, the gutter unchanged")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("sort-lines")
+                .long("sort-lines")
+                .help("Order lines by their bytes, keeping their offsets, so reordered data diffs cleanly"),
+        ).arg(
+            Arg::with_name("crc-delta")
+                .long("crc-delta")
+                .help("Follow every line with its crc32 and whether it changed from the line before"),
+        ).arg(
+            Arg::with_name("cmd")
+                .long("cmd")
+                .value_name("COMMAND")
+                .help("Dump the standard output of a shell command instead of a file")
+                .conflicts_with_all(&["INPUTFILE", "watch", "from-clipboard"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("case")
+                .long("case")
+                .alias("fold-case")
+                .help("Set the case of hex digits in the dump, arrays, --plain and --oneline")
+                .possible_values(&["upper", "lower"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("decode-column")
+                .long("decode-column")
+                .help("Add a column showing the gutter text decoded")
+                .possible_values(&["rot13", "base64", "url"])
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("max-memory")
+                .long("max-memory")
+                .value_name("BYTES")
+                .help("Fail instead of buffering more than BYTES of the dump in memory")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("save-options")
+                .long("save-options")
+                .value_name("PATH")
+                .help("Save the options of this run to PATH, for --replay")
+                .takes_value(true),
+        ).arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .value_name("PATH")
+                .help("Apply the options saved in PATH, explicit options taking precedence")
+                .takes_value(true),
+        );
+
+    #[cfg(feature = "clipboard")]
+    let app = app.arg(
+        Arg::with_name("from-clipboard")
+            .long("from-clipboard")
+            .help("Dump the clipboard content, decoded first if it is hex text"),
+    );
+
+    #[cfg(feature = "sections")]
+    let app = app.arg(
+        Arg::with_name("section")
+            .long("section")
+            .value_name("NAME")
+            .help("Dump only the named ELF or PE section, e.g. .text")
+            .conflicts_with_all(&["range", "skip", "seek-percent"])
+            .takes_value(true),
+    );
+
+    #[cfg(feature = "image")]
+    let app = app.arg(
+        Arg::with_name("image")
+            .long("image")
+            .value_name("PNG")
+            .help(
+                "Render the input as a grayscale png, one pixel per byte and --cols pixels per row",
+            )
+            .takes_value(true),
+    );
+
+    #[cfg(feature = "fuzzy")]
+    let app = app.arg(
+        Arg::with_name("fuzzy-hash")
+            .long("fuzzy-hash")
+            .help("Print an ssdeep-style fuzzy hash of the input instead of dumping"),
+    );
+
+    app
+}
+
+/// In most hex editor applications, the data of the computer file is
+/// represented as hexadecimal values grouped in 4 groups of 4 bytes
+/// (or two groups of 8 bytes), followed by one group of 16 printable ASCII
+/// characters which correspond to each pair of hex values (each byte).
+/// Non-printable ASCII characters (e.g., Bell) and characters that would take
+/// more than one character space (e.g., tab) are typically represented by a
+/// dot (".") in the following ASCII field.
+///
+/// # Arguments
+///
+/// * `matches` - Argument matches from command line.
+pub fn run(matches: ArgMatches) -> Result<()> {
+    let stdout = io::stdout();
+    let sink: Box<dyn Write> = match (matches.value_of("send"), matches.value_of("output")) {
+        _ if matches.is_present("dry-run") => Box::new(io::sink()),
+        (Some(addr), _) => Box::new(connect(addr)?),
+        (None, Some(path)) => Box::new(File::create(path)?),
+        (None, None) => Box::new(stdout.lock()),
+    };
+    let mut out = Prefixed::new(
+        FinalNewline::new(BufWriter::new(sink), matches.is_present("no-eof-newline")),
+        matches.value_of("prefix").unwrap_or(""),
+    );
+    let table = match matches.value_of("map-table") {
+        Some(path) => Some(load_map_table(path)?),
+        None => None,
+    };
+    let timeout = match matches.value_of("timeout") {
+        Some(timeout) => Some(
+            timeout
+                .parse::<f64>()
+                .ok()
+                .filter(|timeout| *timeout > 0.0 && timeout.is_finite())
+                .map(Duration::from_secs_f64)
+                .ok_or_else(|| Error::Application(format!("invalid timeout '{}'", timeout)))?,
+        ),
+        None => None,
+    };
+
+    if let Some(len) = matches.value_of("func") {
+        let mut p: usize = 4;
+        if let Some(places) = matches.value_of("places") {
+            p = places.parse::<usize>().unwrap();
+        }
+        func_out(len.parse::<u64>().unwrap(), p);
+    } else if cfg!(feature = "clipboard") && matches.is_present("from-clipboard") {
+        #[cfg(feature = "clipboard")]
+        {
+            let mut bytes = clipboard_bytes(&mut SystemClipboard)?;
+            remap(&mut bytes, table.as_ref());
+            let len = bytes.len() as u64;
+            dump(
+                &matches,
+                &mut io::Cursor::new(bytes),
+                len,
+                "clipboard",
+                &mut out,
+            )?;
+        }
+    } else if let Some(command) = matches.value_of("cmd") {
+        let mut bytes = command_output(command, timeout)?;
+        remap(&mut bytes, table.as_ref());
+        let len = bytes.len() as u64;
+        let checkpoint = parse_u64_arg(&matches, "checkpoint")?;
+        let mut buf = Checkpoint::new(io::Cursor::new(bytes), checkpoint, io::stderr());
+        dump(&matches, &mut buf, len, command, &mut out)?;
+    } else if matches.value_of("INPUTFILE") == Some("-") {
+        let started = Instant::now();
+        let total_size = parse_u64_arg(&matches, "total-size")?;
+        let mut bytes = match timeout {
+            Some(timeout) => {
+                let stdin = io::stdin().take(total_size.unwrap_or(u64::MAX));
+                read_stream(
+                    io::Cursor::new(read_with_timeout(stdin, timeout)?),
+                    total_size,
+                )?
+            }
+            None => read_stream(io::stdin().lock(), total_size)?,
+        };
+        remap(&mut bytes, table.as_ref());
+        let len = bytes.len() as u64;
+        let checkpoint = parse_u64_arg(&matches, "checkpoint")?;
+        let mut buf = Counter::new(Checkpoint::new(
+            io::Cursor::new(bytes),
+            checkpoint,
+            io::stderr(),
+        ));
+        dump(&matches, &mut buf, len, "stdin", &mut out)?;
+        out.flush()?;
+        if matches.is_present("stats") {
+            print_stats(buf.count, started.elapsed(), &mut io::stderr())?;
+        }
+    } else if let (true, Some(file)) = (matches.is_present("watch"), matches.value_of("INPUTFILE"))
+    {
+        watch(
+            Path::new(file),
+            Duration::from_millis(500),
+            None,
+            &mut out,
+            || match matches.value_of("cols") {
+                Some("auto") => terminal_width(),
+                _ => None,
+            },
+            |w| {
+                let file_len = fs::metadata(file)?.len();
+                let mut buf = BufReader::new(File::open(file)?);
+                dump(&matches, &mut buf, file_len, file, w)
+            },
+        )?;
+    } else if let Some(file) = matches.value_of("INPUTFILE") {
+        let started = Instant::now();
+        let count = match timeout {
+            Some(timeout) => {
+                let bytes = read_with_timeout(File::open(file)?, timeout)?;
+                let len = bytes.len() as u64;
+                dump_input(&matches, io::Cursor::new(bytes), len, file, table, &mut out)?
+            }
+            None => {
+                let file_len = fs::metadata(file)?.len();
+                let buf = BufReader::new(File::open(file)?);
+                dump_input(&matches, buf, file_len, file, table, &mut out)?
+            }
+        };
+        out.flush()?;
+        if matches.is_present("stats") {
+            print_stats(count, started.elapsed(), &mut io::stderr())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Dump an input file, decompressed and remapped through `table` when needed,
+/// and return the number of input bytes read.
+///
+/// # Arguments
+///
+/// * `matches` - Command line arguments.
+/// * `buf` - Input.
+/// * `len` - Input length.
+/// * `name` - Input file name.
+/// * `table` - Byte substitution table, if any.
+/// * `w` - Output writer.
+fn dump_input<R: BufRead + Seek, W: Write>(
+    matches: &ArgMatches,
+    mut buf: R,
+    len: u64,
+    name: &str,
+    table: Option<[u8; 256]>,
+    w: &mut W,
+) -> Result<u64> {
+    let checkpoint = parse_u64_arg(matches, "checkpoint")?;
+    let compression = if matches.is_present("no-decompress") {
+        None
+    } else {
+        detect_compression(Path::new(name), buf.fill_buf()?)
+            .filter(|compression| compression.supported())
+    };
+    Ok(match (compression, table) {
+        (Some(_), _) | (_, Some(_)) => {
+            let mut bytes = match compression {
+                Some(compression) => decompress(buf, compression)?,
+                None => {
+                    let mut bytes = Vec::new();
+                    buf.read_to_end(&mut bytes)?;
+                    bytes
+                }
+            };
+            remap(&mut bytes, table.as_ref());
+            let len = bytes.len() as u64;
+            let mut buf = Counter::new(Checkpoint::new(
+                io::Cursor::new(bytes),
+                checkpoint,
+                io::stderr(),
+            ));
+            dump(matches, &mut buf, len, name, w)?;
+            buf.count
+        }
+        (None, None) => {
+            let mut buf = Counter::new(Checkpoint::new(buf, checkpoint, io::stderr()));
+            dump(matches, &mut buf, len, name, w)?;
+            buf.count
+        }
+    })
+}
+
+/// File offset and size of a named ELF or PE section.
+///
+/// # Arguments
+///
+/// * `bytes` - The whole ELF or PE file.
+/// * `name` - Section name, e.g. `.text`.
+#[cfg(feature = "sections")]
+pub fn find_section(bytes: &[u8], name: &str) -> Result<(u64, u64)> {
+    let invalid = |e: goblin::error::Error| Error::Application(format!("invalid binary: {}", e));
+    let sections: Vec<(String, u64, u64)> = if bytes.starts_with(b"\x7fELF") {
+        let elf = goblin::elf::Elf::parse(bytes).map_err(invalid)?;
+        elf.section_headers
+            .iter()
+            .filter(|header| header.sh_type != goblin::elf::section_header::SHT_NOBITS)
+            .filter_map(|header| {
+                let name = elf.shdr_strtab.get_at(header.sh_name)?;
+                Some((name.to_string(), header.sh_offset, header.sh_size))
+            })
+            .filter(|(name, _, _)| !name.is_empty())
+            .collect()
+    } else if bytes.starts_with(b"MZ") {
+        let pe = goblin::pe::PE::parse(bytes).map_err(invalid)?;
+        pe.sections
+            .iter()
+            .filter_map(|section| {
+                let name = section.name().ok()?;
+                Some((
+                    name.to_string(),
+                    u64::from(section.pointer_to_raw_data),
+                    u64::from(section.size_of_raw_data),
+                ))
+            })
+            .collect()
+    } else {
+        return Err(Error::Application(
+            "--section needs an ELF or PE input".to_string(),
+        ));
+    };
+
+    match sections.iter().find(|(section, _, _)| section == name) {
+        Some((_, offset, size)) => Ok((*offset, *size)),
+        None => {
+            let names: Vec<&str> = sections.iter().map(|(name, _, _)| &name[..]).collect();
+            Err(Error::Application(format!(
+                "no section '{}', available: {}",
+                name,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+/// Write bytes as a grayscale png, one pixel per byte and `width` pixels per
+/// row, the last row padded with black. Returns the image width and height.
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to render.
+/// * `width` - Image width in pixels.
+/// * `w` - Output writer.
+#[cfg(feature = "image")]
+pub fn write_heatmap<W: Write>(bytes: &[u8], width: u32, w: W) -> Result<(u32, u32)> {
+    let width = width.max(1);
+    let height = ((bytes.len() as u64).div_ceil(u64::from(width)) as u32).max(1);
+    let mut pixels = bytes.to_vec();
+    pixels.resize((width * height) as usize, 0x00);
+
+    let png_error = |e: png::EncodingError| Error::Application(format!("png: {}", e));
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&pixels).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+    Ok((width, height))
+}
+
+/// Settings of a `hex: KEY=VALUE ...` modeline, found anywhere in `line`,
+/// e.g. `# hex: cols=16 format=x`.
+///
+/// # Arguments
+///
+/// * `line` - First line of the input.
+pub fn parse_modeline(line: &str) -> Option<Vec<(String, String)>> {
+    let settings = &line[line.find("hex:")? + 4..];
+    settings
+        .split_whitespace()
+        .map(|setting| {
+            let mut parts = setting.splitn(2, '=');
+            Some((parts.next()?.to_string(), parts.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// Apply modeline settings: `cols`, `format` and `offset-format`.
+///
+/// # Arguments
+///
+/// * `settings` - Modeline settings.
+/// * `options` - Options to update.
+pub fn apply_modeline(settings: &[(String, String)], options: &mut Options) -> Result<()> {
+    for (key, value) in settings {
+        let invalid =
+            || Error::Application(format!("invalid modeline setting '{}={}'", key, value));
+        match key.as_str() {
+            "cols" => {
+                options.column_width = parse_number(value)
+                    .filter(|cols| *cols > 0)
+                    .ok_or_else(invalid)?
+            }
+            "format" => {
+                options.format = match parse_format(value) {
+                    Format::Unknown => return Err(invalid()),
+                    format => format,
+                }
+            }
+            "offset-format" => {
+                options.offset_format = parse_offset_format(value).ok_or_else(invalid)?
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(())
+}
+
+/// Report the effective settings of a dump, for `--dry-run`.
+///
+/// # Arguments
+///
+/// * `name` - Input name.
+/// * `skip` - Offset of the first dumped byte.
+/// * `len` - Number of bytes to dump.
+/// * `options` - Resolved dump options.
+/// * `output` - Destination of the dump.
+/// * `w` - Output writer, stderr to keep stdout empty.
+pub fn print_settings<W: Write>(
+    name: &str,
+    skip: u64,
+    len: u64,
+    options: &Options,
+    output: &str,
+    w: &mut W,
+) -> Result<()> {
+    writeln!(w, "input: {}", name)?;
+    writeln!(w, "skip: {}", offset(skip))?;
+    writeln!(w, "len: {}", len)?;
+    writeln!(w, "format: {:?}", options.format)?;
+    writeln!(w, "cols: {}", options.column_width)?;
+    writeln!(w, "output: {}", output)?;
+    Ok(())
+}
+
+/// Number of bytes dumped for a requested `--len`: never more than the
+/// input holds, so that summaries and limits see the real length.
+///
+/// # Arguments
+///
+/// * `requested` - Requested length.
+/// * `input_len` - Input length.
+pub fn effective_len(requested: u64, input_len: u64) -> u64 {
+    requested.min(input_len)
+}
+
+/// Shortest block size of a fuzzy hash.
+#[cfg(feature = "fuzzy")]
+const FUZZY_MIN_BLOCK: u32 = 3;
+
+/// Longest signature of a fuzzy hash.
+#[cfg(feature = "fuzzy")]
+const FUZZY_SIGNATURE_LEN: usize = 64;
+
+/// Rolling hash window of a fuzzy hash.
+#[cfg(feature = "fuzzy")]
+const FUZZY_WINDOW: usize = 7;
+
+/// Rolling hash over the last `FUZZY_WINDOW` bytes, triggering the end of
+/// fuzzy hash pieces
+#[cfg(feature = "fuzzy")]
+#[derive(Default)]
+struct RollingHash {
+    window: [u8; FUZZY_WINDOW],
+    n: usize,
+    h1: u32,
+    h2: u32,
+    h3: u32,
+}
+
+#[cfg(feature = "fuzzy")]
+impl RollingHash {
+    fn update(&mut self, b: u8) -> u32 {
+        let c = u32::from(b);
+        let out = u32::from(self.window[self.n % FUZZY_WINDOW]);
+        self.h2 = self
+            .h2
+            .wrapping_sub(self.h1)
+            .wrapping_add(FUZZY_WINDOW as u32 * c);
+        self.h1 = self.h1.wrapping_add(c).wrapping_sub(out);
+        self.window[self.n % FUZZY_WINDOW] = b;
+        self.n += 1;
+        self.h3 = (self.h3 << 5) ^ c;
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
+}
+
+/// Context-triggered piecewise hash of `bytes`, as `BLOCK:SIG1:SIG2` in the
+/// ssdeep format.
+///
+/// # Arguments
+///
+/// * `bytes` - Input bytes.
+#[cfg(feature = "fuzzy")]
+pub fn fuzzy_hash(bytes: &[u8]) -> String {
+    const B64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    const HASH_INIT: u32 = 0x2802_1967;
+    const HASH_PRIME: u32 = 0x0100_0193;
+    let mut block = FUZZY_MIN_BLOCK;
+    while u64::from(block) * (FUZZY_SIGNATURE_LEN as u64) < bytes.len() as u64 {
+        block *= 2;
+    }
+    loop {
+        let mut roll = RollingHash::default();
+        let (mut h1, mut h2) = (HASH_INIT, HASH_INIT);
+        let (mut sig1, mut sig2) = (String::new(), String::new());
+        let mut rolled = 0;
+        for b in bytes {
+            h1 = h1.wrapping_mul(HASH_PRIME) ^ u32::from(*b);
+            h2 = h2.wrapping_mul(HASH_PRIME) ^ u32::from(*b);
+            rolled = roll.update(*b);
+            if rolled % block == block - 1 && sig1.len() < FUZZY_SIGNATURE_LEN - 1 {
+                sig1.push(B64[(h1 % 64) as usize] as char);
+                h1 = HASH_INIT;
+            }
+            if rolled % (2 * block) == 2 * block - 1 && sig2.len() < FUZZY_SIGNATURE_LEN / 2 - 1 {
+                sig2.push(B64[(h2 % 64) as usize] as char);
+                h2 = HASH_INIT;
+            }
+        }
+        if rolled != 0 {
+            sig1.push(B64[(h1 % 64) as usize] as char);
+            sig2.push(B64[(h2 % 64) as usize] as char);
+        }
+        if block > FUZZY_MIN_BLOCK && sig1.len() < FUZZY_SIGNATURE_LEN / 2 {
+            block /= 2;
+            continue;
+        }
+        return format!("{}:{}:{}", block, sig1, sig2);
+    }
+}
+
+/// Similarity score of two signatures of the same block size, 0 to 100.
+#[cfg(feature = "fuzzy")]
+fn fuzzy_signature_score(a: &str, b: &str, block: u32) -> u32 {
+    // runs longer than three characters carry little information
+    let squeeze = |s: &str| -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        for c in s.bytes() {
+            if out.len() < 3 || out[out.len() - 3..].iter().any(|x| *x != c) {
+                out.push(c);
+            }
+        }
+        out
+    };
+    let (a, b) = (squeeze(a), squeeze(b));
+    let common = a
+        .windows(FUZZY_WINDOW)
+        .any(|window| b.windows(FUZZY_WINDOW).any(|other| window == other));
+    if !common {
+        return 0;
+    }
+    // edit distance, a substitution costing a deletion and an insertion
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous + if x == y { 0 } else { 2 };
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    let distance = row[b.len()] as u32;
+    let score = distance * FUZZY_SIGNATURE_LEN as u32 / (a.len() + b.len()) as u32;
+    let score = 100 - (100 * score / FUZZY_SIGNATURE_LEN as u32).min(100);
+    // small block sizes make short matches likely, cap their score
+    let cap_block = (99 + FUZZY_WINDOW as u32) / FUZZY_WINDOW as u32 * FUZZY_MIN_BLOCK;
+    if block >= cap_block {
+        score
+    } else {
+        score.min(block / FUZZY_MIN_BLOCK * a.len().min(b.len()) as u32)
+    }
+}
+
+/// Similarity score of two fuzzy hashes, 0 for unrelated inputs up to 100
+/// for identical ones. Hashes whose block sizes are neither equal nor a
+/// factor of two apart score 0.
+///
+/// # Arguments
+///
+/// * `a` - First fuzzy hash.
+/// * `b` - Second fuzzy hash.
+#[cfg(feature = "fuzzy")]
+pub fn fuzzy_compare(a: &str, b: &str) -> Option<u32> {
+    let parse = |hash: &str| -> Option<(u32, String, String)> {
+        let mut parts = hash.splitn(3, ':');
+        let block = parts.next()?.parse::<u32>().ok()?;
+        Some((block, parts.next()?.to_string(), parts.next()?.to_string()))
+    };
+    let (block_a, a1, a2) = parse(a)?;
+    let (block_b, b1, b2) = parse(b)?;
+    Some(if block_a == block_b {
+        fuzzy_signature_score(&a1, &b1, block_a).max(fuzzy_signature_score(&a2, &b2, block_a * 2))
+    } else if block_a == block_b * 2 {
+        fuzzy_signature_score(&a1, &b2, block_a)
+    } else if block_b == block_a * 2 {
+        fuzzy_signature_score(&a2, &b1, block_b)
+    } else {
+        0
+    })
+}
+
+/// Dump an input as requested on the command line.
+///
+/// # Arguments
+///
+/// * `matches` - Argument matches from command line.
+/// * `buf` - Input, positioned at its start.
+/// * `input_len` - Input length.
+/// * `name` - Input name, e.g. the file path.
+/// * `w` - Output writer.
+fn dump<R: BufRead + Seek, W: Write>(
+    matches: &ArgMatches,
+    buf: &mut R,
+    input_len: u64,
+    name: &str,
+    w: &mut W,
+) -> Result<()> {
+    let mut options: Options = Options::new();
+    let mut max_memory = parse_u64_arg(matches, "max-memory")?;
+    if let Some(columns) = matches.value_of("cols") {
+        if columns == "auto" {
+            options.auto_columns = true;
+        } else {
+            options.column_width = parse_number(columns).ok_or_else(|| {
+                Error::Application(format!("invalid value '{}' for --cols", columns))
+            })?;
+        }
+    }
+
+    let mut buf_len = input_len;
+    if let Some(length) = parse_u64_arg(matches, "len")? {
+        buf_len = effective_len(length, input_len);
+    }
+
+    let mut skip: u64 = 0x0;
+    if let Some(range) = matches.value_of("range") {
+        let (start, end) = parse_range(range)
+            .ok_or_else(|| Error::Application(format!("invalid range '{}'", range)))?;
+        let end = end.unwrap_or(input_len).min(input_len).max(start);
+        skip = start;
+        buf_len = if matches.is_present("len") {
+            buf_len.min(end - start)
+        } else {
+            end - start
+        };
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if let Some(start) = parse_u64_arg(matches, "skip")? {
+        skip = start.min(input_len);
+        buf_len = if matches.is_present("len") {
+            buf_len.min(input_len - skip)
+        } else {
+            input_len - skip
+        };
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    #[cfg(feature = "sections")]
+    {
+        if let Some(section) = matches.value_of("section") {
+            check_memory(input_len.min(MAX_PAGE_LEN), max_memory)?;
+            let mut bytes: Vec<u8> = Vec::new();
+            buf.seek(SeekFrom::Start(0))?;
+            buf.by_ref().take(MAX_PAGE_LEN).read_to_end(&mut bytes)?;
+            let (start, size) = find_section(&bytes, section)?;
+            skip = start.min(input_len);
+            buf_len = size.min(input_len - skip);
+            buf.seek(SeekFrom::Start(skip))?;
+            options
+                .annotations
+                .entry(skip)
+                .or_default()
+                .push(format!("section {}, {} bytes", section, buf_len));
+        }
+    }
+
+    if let Some(percent) = matches.value_of("seek-percent") {
+        let percent = percent.parse::<f64>().map_err(|_| {
+            Error::Application(format!("invalid value '{}' for --seek-percent", percent))
+        })?;
+        skip = seek_percent_offset(Some(input_len), percent)?;
+        buf_len = if matches.is_present("len") {
+            buf_len.min(input_len - skip)
+        } else {
+            input_len - skip
+        };
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if let Some(field) = matches.value_of("len-field") {
+        let (at, width, endian) = parse_len_field(field)
+            .ok_or_else(|| Error::Application(format!("invalid length field '{}'", field)))?;
+        let len = read_len_field(buf, at, width, endian)?;
+        skip = (at + width as u64).min(input_len);
+        buf_len = len.min(input_len - skip);
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if matches.is_present("stop-at-null") {
+        buf_len = len_to_null(buf, buf_len)?;
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if let Some(offsets) = matches.values_of("uuid-at") {
+        for value in offsets {
+            let at = parse_number(value).ok_or_else(|| {
+                Error::Application(format!("invalid value '{}' for --uuid-at", value))
+            })?;
+            let mut uuid = [0u8; 16];
+            buf.seek(SeekFrom::Start(at))?;
+            buf.read_exact(&mut uuid).map_err(|_| {
+                Error::Application(format!("no 16 bytes for a uuid at {}", offset(at)))
+            })?;
+            options.annotations.entry(at).or_default().push(format!(
+                "uuid {}",
+                format_uuid(&uuid, matches.is_present("uuid-ms"))
+            ));
+        }
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if matches.is_present("modeline") {
+        let mut first_line = Vec::new();
+        buf.seek(SeekFrom::Start(0))?;
+        buf.by_ref().take(1024).read_until(b'\n', &mut first_line)?;
+        buf.seek(SeekFrom::Start(skip))?;
+        if let Some(settings) = parse_modeline(&String::from_utf8_lossy(&first_line)) {
+            let columns = options.column_width;
+            apply_modeline(&settings, &mut options)?;
+            if matches.is_present("cols") {
+                options.column_width = columns;
+            }
+        }
+    }
+
+    if let Some(offsets) = matches.values_of("leb128") {
+        for value in offsets {
+            let at = parse_number(value).ok_or_else(|| {
+                Error::Application(format!("invalid value '{}' for --leb128", value))
+            })?;
+            let mut encoded = Vec::new();
+            buf.seek(SeekFrom::Start(at))?;
+            buf.by_ref().take(10).read_to_end(&mut encoded)?;
+            let (unsigned, signed, len) = decode_leb128(&encoded)
+                .ok_or_else(|| Error::Application(format!("no valid leb128 at {}", offset(at))))?;
+            options.annotations.entry(at).or_default().push(format!(
+                "leb128, {} bytes: unsigned {}, signed {}",
+                len, unsigned, signed
+            ));
+        }
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if let Some(offsets) = matches.values_of("der") {
+        for value in offsets {
+            let at = parse_number(value).ok_or_else(|| {
+                Error::Application(format!("invalid value '{}' for --der", value))
+            })?;
+            let mut encoded = Vec::new();
+            buf.seek(SeekFrom::Start(at))?;
+            buf.by_ref().take(16).read_to_end(&mut encoded)?;
+            let header = decode_der_header(&encoded).ok_or_else(|| {
+                Error::Application(format!("no valid der header at {}", offset(at)))
+            })?;
+            options
+                .annotations
+                .entry(at)
+                .or_default()
+                .push(der_annotation(&header, at));
+        }
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    if let Some(format) = matches
+        .value_of("byte-format")
+        .or_else(|| matches.value_of("format"))
+    {
+        options.format = parse_format(format);
+    }
+    if let Format::Pointer = options.format {
+        options.format = Format::LowerHex;
+        options.pointers = true;
+    }
+    let upper = matches.value_of("case") == Some("upper");
+    options.format = match (options.format, matches.value_of("case")) {
+        (Format::LowerHex, Some("upper")) => Format::UpperHex,
+        (Format::UpperHex, Some("lower")) => Format::LowerHex,
+        (format, _) => format,
+    };
+    if matches.is_present("ptrs") {
+        options.pointers = true;
+    }
+    if options.pointers && !matches.is_present("cols") {
+        options.column_width = 16;
+    }
+    if let Some(format) = matches.value_of("offset-format") {
+        options.offset_format = parse_offset_format(format)
+            .ok_or_else(|| Error::Application(format!("invalid offset format '{}'", format)))?;
+    }
+
+    let to_terminal =
+        io::stdout().is_terminal() && !matches.is_present("send") && !matches.is_present("output");
+    if let Some(color) = matches.value_of("color") {
+        options.colorize = parse_color_mode(color, to_terminal)
+            .ok_or_else(|| Error::Application(format!("invalid color mode '{}'", color)))?;
+    }
+    if let Some(accent) = matches.value_of("accent") {
+        options.accent = parse_color_name(accent)
+            .ok_or_else(|| Error::Application(format!("unknown color '{}'", accent)))?;
+    }
+
+    if options.colorize {
+        options.hyperlink = hyperlink_template(matches, to_terminal);
+    }
+
+    options.record_size = parse_u64_arg(matches, "record-size")?;
+    if options.record_size == Some(0) {
+        return Err(Error::Application(
+            "--record-size must be greater than 0".to_string(),
+        ));
+    }
+    options.record_label = matches.value_of("record-label").map(String::from);
+
+    if let Some(delim) = matches.value_of("gutter-delim") {
+        options.gutter_delim = parse_gutter_delim(delim)
+            .ok_or_else(|| Error::Application(format!("invalid gutter delimiters '{}'", delim)))?;
+    }
+
+    options.escape_gutter = matches.is_present("escape-gutter");
+    options.bitmask = matches.is_present("bitmask");
+    options.swap_words = parse_u64_arg(matches, "swap-words")?;
+    options.no_summary = matches.is_present("no-summary");
+    if matches.is_present("alt-group-color") {
+        options.alt_group = Some(parse_u64_arg(matches, "group-size")?.unwrap_or(4).max(1));
+    }
+
+    options.offset_last = matches.is_present("offset-last");
+    options.compact = matches.is_present("compact");
+    if matches.is_present("binary-compact") {
+        options.format = Format::Binary;
+        options.compact = true;
+    }
+    options.line_numbers = matches.is_present("line-numbers");
+    if matches.is_present("percent") {
+        options.percent_of = Some(input_len);
+    }
+    options.zebra = matches.is_present("zebra");
+    options.no_trailing_space = matches.is_present("no-trailing-space");
+    if options.auto_columns {
+        options.column_width = fit_columns(terminal_width().unwrap_or(80), &options);
+    }
+    if let Some(baseline) = matches
+        .value_of("diff")
+        .or_else(|| matches.value_of("baseline"))
+    {
+        // read one byte past the limit to tell an oversized baseline
+        let mut bytes = Vec::new();
+        File::open(baseline)?
+            .take(max_memory.map_or(u64::MAX, |max| max.saturating_add(1)))
+            .read_to_end(&mut bytes)?;
+        check_memory(bytes.len() as u64, max_memory)?;
+        // the dump shares the limit with the baseline
+        max_memory = max_memory.map(|max| max - bytes.len() as u64);
+        options.baseline = Some(bytes);
+    }
+    if let Some(decoding) = matches.value_of("decode-column") {
+        options.decode_column = Some(
+            parse_decoding(decoding)
+                .ok_or_else(|| Error::Application(format!("unknown decoding '{}'", decoding)))?,
+        );
+    }
+    if let Some(codepage) = matches.value_of("codepage") {
+        options.codepage = Some(
+            parse_codepage(codepage)
+                .ok_or_else(|| Error::Application(format!("unknown code page '{}'", codepage)))?,
+        );
+    }
+
+    options.quote_strings = matches.is_present("quote-strings");
+    if let Some(stride) = parse_u64_arg(matches, "addr-stride")? {
+        if stride == 0 {
+            return Err(Error::Application(
+                "--addr-stride must be greater than 0".to_string(),
+            ));
+        }
+        options.addr_stride = stride;
+    }
+
+    if matches.is_present("dry-run") {
+        let output = matches
+            .value_of("send")
+            .or_else(|| matches.value_of("output"))
+            .unwrap_or("stdout");
+        return print_settings(name, skip, buf_len, &options, output, &mut io::stderr());
+    }
+
+    if matches.is_present("auto") {
+        // judge only bytes that will be dumped
+        let sample_len = buf.fill_buf()?.len().min(buf_len.min(1024) as usize);
+        if detect_text(&buf.fill_buf()?[..sample_len]) {
+            options.utf8_gutter = true;
+            if !matches.is_present("cols") {
+                options.column_width = 16;
+            }
+        }
+    }
+
+    if matches.is_present("follow") {
+        if matches.value_of("INPUTFILE") == Some("-") {
+            return Err(Error::Application(
+                "--follow needs an input file, not stdin".to_string(),
+            ));
+        }
+        return follow(
+            Path::new(name),
+            skip,
+            &options,
+            Duration::from_millis(250),
+            None,
+            w,
+        );
+    }
+
+    if let Some(assertions) = matches.values_of("assert") {
+        let layout = matches.value_of("struct").unwrap_or_default();
+        let fields = parse_layout(layout)
+            .ok_or_else(|| Error::Application(format!("invalid struct layout '{}'", layout)))?;
+        let endian = parse_endian(matches.value_of("endian").unwrap_or("little"))
+            .ok_or_else(|| Error::Application("invalid endianness".to_string()))?;
+        check_memory(buf_len.min(MAX_PAGE_LEN), max_memory)?;
+        let mut bytes = Vec::new();
+        buf.take(buf_len.min(MAX_PAGE_LEN))
+            .read_to_end(&mut bytes)?;
+        for assertion in assertions {
+            check_assertion(&bytes, &fields, endian, assertion)?;
+            writeln!(w, "{}: ok", assertion)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(fill) = matches.value_of("expect-fill") {
+        let fill = parse_number(fill)
+            .filter(|fill| *fill <= 0xff)
+            .ok_or_else(|| Error::Application(format!("invalid fill value '{}'", fill)))?
+            as u8;
+        return match check_fill(buf, skip, buf_len, fill)? {
+            None => {
+                writeln!(
+                    w,
+                    "{}..{}: all {}",
+                    offset(skip),
+                    offset(skip + buf_len),
+                    hex_lower_hex(fill)
+                )?;
+                Ok(())
+            }
+            Some((mismatch, b)) => Err(Error::Mismatch(format!(
+                "byte at {} is {}, expected {}",
+                offset(mismatch),
+                hex_lower_hex(b),
+                hex_lower_hex(fill)
+            ))),
+        };
+    }
+
+    if matches.is_present("raw") {
+        if let Some(path) = matches.value_of("ranges-file") {
+            let ranges = parse_ranges_file(&fs::read_to_string(path)?)?;
+            carve_ranges(buf, skip + buf_len, &ranges, w)?;
+            return Ok(());
+        }
+        let (low, high) = match matches.value_of("extract-range") {
+            Some(range) => parse_value_range(range)
+                .ok_or_else(|| Error::Application(format!("invalid value range '{}'", range)))?,
+            None => (0x00, u8::MAX),
+        };
+        if matches.is_present("strip-bom") {
+            buf_len -= strip_bom(buf, buf_len)?;
+        }
+        extract_bytes(buf, buf_len, low, high, w)?;
+        return Ok(());
+    }
+
+    if matches.is_present("strings-utf16") {
+        let min_len = parse_u64_arg(matches, "min-len")?.unwrap_or(4) as usize;
+        for (at, string) in utf16_strings(buf, skip, buf_len, min_len)? {
+            writeln!(w, "{}: {}", offset(at), string)?;
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "image")]
+    {
+        if let Some(image) = matches.value_of("image") {
+            if buf_len > MAX_PAGE_LEN {
+                return Err(Error::Application(format!(
+                    "{} bytes exceed the {}MB in-memory limit, render a part with --len",
+                    buf_len,
+                    MAX_PAGE_LEN >> 20
+                )));
+            }
+            check_memory(buf_len, max_memory)?;
+            let mut bytes: Vec<u8> = Vec::new();
+            buf.take(buf_len).read_to_end(&mut bytes)?;
+            let (width, height) =
+                write_heatmap(&bytes, options.column_width as u32, File::create(image)?)?;
+            writeln!(w, "{}: {}x{} pixels", image, width, height)?;
+            return Ok(());
+        }
+    }
+
+    #[cfg(feature = "fuzzy")]
+    {
+        if matches.is_present("fuzzy-hash") {
+            check_memory(buf_len.min(MAX_PAGE_LEN), max_memory)?;
+            let mut bytes: Vec<u8> = Vec::new();
+            buf.take(buf_len.min(MAX_PAGE_LEN))
+                .read_to_end(&mut bytes)?;
+            writeln!(w, "{} {}", fuzzy_hash(&bytes), name)?;
+            return Ok(());
+        }
+    }
+
+    if matches.is_present("require-printable") {
+        return match first_nonprintable(buf, skip, buf_len)? {
+            None => {
+                writeln!(
+                    w,
+                    "{}..{}: all printable",
+                    offset(skip),
+                    offset(skip + buf_len)
+                )?;
+                Ok(())
+            }
+            Some((at, b)) => Err(Error::Mismatch(format!(
+                "non-printable byte {} at {}",
+                hex_lower_hex(b),
+                offset(at)
+            ))),
+        };
+    }
+
+    if matches.is_present("distribution-map") {
+        let segments = parse_u64_arg(matches, "segments")?.unwrap_or(MINIMAP_HEIGHT as u64);
+        for (at, counts) in distribution_map(buf, skip, buf_len, segments)? {
+            let class = counts.dominant();
+            if options.colorize {
+                writeln!(
+                    w,
+                    "{}: {} {}",
+                    offset(at),
+                    class_color(class).paint("\u{2588}".repeat(16)),
+                    class
+                )?;
+            } else {
+                writeln!(w, "{}: {}", offset(at), class)?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(other) = matches.value_of("xor-with") {
+        let other_len = fs::metadata(other)?.len();
+        let mut other = BufReader::new(File::open(other)?);
+        let other_skip = skip.min(other_len);
+        other.seek(SeekFrom::Start(other_skip))?;
+        let mut other_len = other_len - other_skip;
+        if matches.is_present("len") {
+            other_len = other_len.min(buf_len);
+        }
+        if buf_len.max(other_len) > MAX_PAGE_LEN {
+            return Err(Error::Application(format!(
+                "{} bytes exceed the {}MB in-memory limit, xor a part with --len",
+                buf_len.max(other_len),
+                MAX_PAGE_LEN >> 20
+            )));
+        }
+        // both inputs, their xor and its page
+        let xored_len = buf_len.max(other_len);
+        check_memory(
+            (buf_len + other_len).saturating_add(page_memory(xored_len, options.column_width)),
+            max_memory,
+        )?;
+        let mut a = Vec::new();
+        buf.take(buf_len).read_to_end(&mut a)?;
+        let mut b = Vec::new();
+        other.take(other_len).read_to_end(&mut b)?;
+        let xored = xor_bytes(&a, &b);
+        let len = xored.len() as u64;
+        let page = buf_to_page(&mut io::Cursor::new(xored), skip, len, options.column_width)?;
+        return print_page(&page, &options, w);
+    }
+
+    if let Some(other) = matches.value_of("interleave") {
+        let other_len = fs::metadata(other)?.len();
+        let mut other = BufReader::new(File::open(other)?);
+        let other_skip = skip.min(other_len);
+        other.seek(SeekFrom::Start(other_skip))?;
+        let other_len = buf_len.min(other_len - other_skip);
+        check_memory(
+            page_memory(buf_len, options.column_width)
+                .saturating_add(page_memory(other_len, options.column_width)),
+            max_memory,
+        )?;
+        let a = buf_to_page(buf, skip, buf_len, options.column_width)?;
+        let b = buf_to_page(&mut other, other_skip, other_len, options.column_width)?;
+        return print_interleaved(&a, &b, &options, w);
+    }
+
+    if matches.is_present("histogram") {
+        let by_freq = matches.value_of("histogram-sort") != Some("value");
+        for (b, count) in histogram_order(&histogram(buf, buf_len)?, by_freq) {
+            writeln!(w, "{}: {}", hex_lower_hex(b), count)?;
+        }
+        return Ok(());
+    }
+
+    if matches.is_present("html") {
+        check_memory(page_memory(buf_len, options.column_width), max_memory)?;
+        let page = buf_to_page(buf, skip, buf_len, options.column_width)?;
+        return write_html(&page, matches.is_present("tooltips"), w);
+    }
+
+    if matches.is_present("intel-hex") {
+        return write_intel_hex(buf, skip, buf_len, w);
+    }
+
+    if matches.is_present("srec") {
+        let address_len = match parse_u64_arg(matches, "srec-width")? {
+            Some(16) => 2,
+            Some(24) => 3,
+            Some(32) => 4,
+            Some(bits) => {
+                return Err(Error::Application(format!(
+                    "invalid s-record address width {}, expected 16, 24 or 32",
+                    bits
+                )))
+            }
+            None if skip + buf_len <= 1 << 16 => 2,
+            None if skip + buf_len <= 1 << 24 => 3,
+            None => 4,
+        };
+        let header = Path::new(name)
+            .file_name()
+            .map_or(name.into(), |name| name.to_string_lossy());
+        return write_srec(buf, skip, buf_len, address_len, &header, w);
+    }
+
+    if matches.is_present("period") {
+        check_memory(buf_len.min(MAX_PAGE_LEN), max_memory)?;
+        let mut bytes: Vec<u8> = Vec::new();
+        buf.take(buf_len.min(MAX_PAGE_LEN))
+            .read_to_end(&mut bytes)?;
+        let max_period = parse_u64_arg(matches, "max-period")?.unwrap_or(4096) as usize;
+        return match detect_period(&bytes, max_period, 0.9) {
+            Some((period, share)) => {
+                writeln!(
+                    w,
+                    "period: {} ({:.2}% of bytes repeat)",
+                    period,
+                    share * 100.0
+                )?;
+                Ok(())
+            }
+            None => Err(Error::NotFound(format!(
+                "no period up to {} bytes",
+                max_period
+            ))),
+        };
+    }
+
+    if let Some(other) = matches.value_of("similarity") {
+        let mut other = BufReader::new(File::open(other)?);
+        other.seek(SeekFrom::Start(skip))?;
+        let (same, total) = similarity(buf.take(buf_len), other.take(buf_len))?;
+        let percent = if total == 0 {
+            100.0
+        } else {
+            same as f64 * 100.0 / total as f64
+        };
+        writeln!(
+            w,
+            "similarity: {:.2}%, {} of {} bytes differ",
+            percent,
+            total - same,
+            total
+        )?;
+        return Ok(());
+    }
+
+    if let Some(record_size) = parse_u64_arg(matches, "column-hash")? {
+        if record_size == 0 {
+            return Err(Error::Application(
+                "--column-hash must be greater than 0".to_string(),
+            ));
+        }
+        for column in column_summaries(buf, buf_len, record_size)? {
+            if column.distinct == 1 {
+                writeln!(
+                    w,
+                    "{:>4}: crc32 {:08x}, constant {}",
+                    column.position,
+                    column.hash,
+                    hex_lower_hex(column.first)
+                )?;
+            } else {
+                writeln!(
+                    w,
+                    "{:>4}: crc32 {:08x}, {} distinct values",
+                    column.position, column.hash, column.distinct
+                )?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(block_size) = parse_u64_arg(matches, "block-hash")? {
+        if block_size == 0 {
+            return Err(Error::Application(
+                "--block-hash must be greater than 0".to_string(),
+            ));
+        }
+        for (at, hash) in block_hashes(buf, skip, buf_len, block_size)? {
+            writeln!(w, "{}: crc32 {:08x}", offset(at), hash)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(format) = matches.value_of("fixed") {
+        let (m, n) = parse_fixed(format).ok_or_else(|| {
+            Error::Application(format!("invalid fixed-point format '{}'", format))
+        })?;
+        let endian = parse_endian(matches.value_of("endian").unwrap_or("little"))
+            .ok_or_else(|| Error::Application("invalid endianness".to_string()))?;
+        let places = parse_u64_arg(matches, "places")?.unwrap_or(4) as usize;
+        let width = ((m + n) / 8) as usize;
+        check_memory(buf_len, max_memory)?;
+        let mut bytes = Vec::new();
+        buf.take(buf_len).read_to_end(&mut bytes)?;
+        let values = bytes.chunks_exact(width);
+        let remainder = values.remainder();
+        for (i, value) in values.enumerate() {
+            writeln!(
+                w,
+                "{}: {:.*}",
+                offset(skip + (i * width) as u64),
+                places,
+                read_fixed(value, n, endian)
+            )?;
+        }
+        if !remainder.is_empty() {
+            let raw: Vec<String> = remainder.iter().map(|b| hex_lower_hex(*b)).collect();
+            writeln!(
+                w,
+                "{}: {} (incomplete value)",
+                offset(skip + (bytes.len() - remainder.len()) as u64),
+                raw.join(" ")
+            )?;
+        }
+        return Ok(());
+    }
+
+    if matches.is_present("null-runs") {
+        let min_run = parse_u64_arg(matches, "min-run")?.unwrap_or(1);
+        for (start, len) in null_runs(buf, skip, buf_len, min_run)? {
+            writeln!(w, "offset {}, length {}", offset(start), len)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(lengths) = matches.value_of("insn-lengths") {
+        // a comma list, or a sidecar file listing the lengths
+        let lengths = match parse_lengths(lengths) {
+            Some(parsed) => parsed,
+            None => parse_lengths(&fs::read_to_string(lengths)?).ok_or_else(|| {
+                Error::Application(format!("invalid instruction lengths in '{}'", lengths))
+            })?,
+        };
+        options.insn_boundaries = insn_boundaries(skip, &lengths);
+        options.insn_boundaries.sort_unstable();
+    }
+
+    if matches.is_present("minimap") {
+        buf.seek(SeekFrom::Start(0))?;
+        options.minimap = minimap_buckets(buf, input_len, MINIMAP_HEIGHT)?;
+        buf.seek(SeekFrom::Start(skip))?;
+    }
+
+    match matches.occurrences_of("v") {
+        0 => write!(w, "")?,
+        1 => write!(w, "verbose 1")?,
+        2 => write!(w, "verbose 2")?,
+        _ => write!(w, "verbose max")?,
+    }
+
+    let repeat = parse_u64_arg(matches, "repeat")?.unwrap_or(1);
+    if repeat == 0 {
+        return Err(Error::Application(
+            "--repeat must be greater than 0".to_string(),
+        ));
+    }
+
+    // a resumable dump buffers a line at a time, the others the whole page
+    let needed = if matches.is_present("state") {
+        page_memory(buf_len.min(options.column_width), options.column_width)
+    } else {
+        page_memory(buf_len.saturating_mul(repeat), options.column_width)
+    };
+    check_memory(needed, max_memory)?;
+
+    // array output mode is mutually exclusive
+    if let Some(array) = matches.value_of("array") {
+        let array_format = array;
+        let page = repeat_page(
+            buf_to_page(buf, skip, buf_len, options.column_width)?,
+            repeat,
+            options.column_width,
+        )?;
+        let highlight = options.colorize && to_terminal;
+        let baseline = options.baseline.as_ref().filter(|_| highlight);
+        print_array(
+            &page,
+            array_format,
+            baseline.map(|b| &b[..]),
+            matches.is_present("const"),
+            upper,
+            w,
+        )?;
+    } else if matches.is_present("plain") || matches.is_present("oneline") {
+        let page = repeat_page(
+            buf_to_page(buf, skip, buf_len, options.column_width)?,
+            repeat,
+            options.column_width,
+        )?;
+        if matches.is_present("plain") {
+            print_plain(&page, matches.value_of("sep-str").unwrap_or(" "), upper, w)?;
+        } else {
+            print_oneline(&page, matches.value_of("sep-str").unwrap_or(""), upper, w)?;
+        }
+    } else if matches.is_present("jsonl") {
+        let page = repeat_page(
+            buf_to_page(buf, skip, buf_len, options.column_width)?,
+            repeat,
+            options.column_width,
+        )?;
+        for line in page.body.iter() {
+            print_jsonl_line(line, w)?;
+        }
+    } else if let Some(state) = matches.value_of("state") {
+        dump_resumable(
+            buf,
+            skip,
+            buf_len,
+            repeat,
+            &options,
+            Progress {
+                path: Path::new(state),
+                resume: matches.is_present("resume"),
+                save_every: STATE_SAVE_LINES,
+            },
+            w,
+        )?;
+    } else {
+        let mut page = repeat_page(
+            buf_to_page(buf, skip, buf_len, options.column_width)?,
+            repeat,
+            options.column_width,
+        )?;
+        if let Some(marker) = matches.value_of("until-ascii") {
+            page = until_ascii(page, marker);
+        }
+        if let Some(pattern) = matches.value_of("mark") {
+            let pattern = parse_hex_input(pattern)
+                .ok_or_else(|| Error::Application(format!("invalid hex pattern '{}'", pattern)))?;
+            options.marks = find_marks(&page.to_bytes(), page.offset, &pattern);
+        }
+        if matches.is_present("trim-trailing") {
+            options.trimmed_lines = trim_trailing(&mut page);
+        }
+        if matches.is_present("rle") {
+            let min_run = parse_u64_arg(matches, "rle-min")?.unwrap_or(4).max(2);
+            for (at, b, len) in byte_runs(&page.to_bytes(), page.offset, min_run) {
+                options.annotations.entry(at).or_default().push(format!(
+                    "{}\u{d7}{}",
+                    hex_lower_hex(b),
+                    len
+                ));
+            }
+        }
+        if let Some(align) = parse_u64_arg(matches, "align")? {
+            if align == 0 {
+                return Err(Error::Application(
+                    "--align must be greater than 0".to_string(),
+                ));
+            }
+            page = align_page(page, align, options.column_width);
+        }
+        if let Some(record_size) = options.record_size {
+            page = split_records(page, record_size, options.column_width);
+        }
+        if let Some(delimiter) = matches.value_of("split-on") {
+            let delimiter = parse_number(delimiter)
+                .filter(|delimiter| *delimiter <= 0xff)
+                .ok_or_else(|| Error::Application(format!("invalid delimiter '{}'", delimiter)))?
+                as u8;
+            let records = delimited_records(&page.to_bytes(), page.offset, delimiter);
+            for (n, (at, len)) in records.iter().enumerate() {
+                options
+                    .annotations
+                    .entry(*at)
+                    .or_default()
+                    .push(format!("record {}, {} bytes", n, len));
+            }
+            options.record_starts = records.iter().map(|(at, _)| *at).collect();
+            page = split_at(page, &options.record_starts, options.column_width);
+            if matches.is_present("hide-delimiter") {
+                page = drop_delimiters(page, delimiter);
+            }
+        }
+        if matches.is_present("tlv") {
+            let widths = matches.value_of("tlv-widths").unwrap_or("1:1");
+            let (type_width, length_width) = parse_tlv_widths(widths).ok_or_else(|| {
+                Error::Application(format!(
+                    "invalid tlv widths '{}', expected TYPE:LENGTH in bytes",
+                    widths
+                ))
+            })?;
+            let endian = parse_endian(matches.value_of("endian").unwrap_or("big"))
+                .ok_or_else(|| Error::Application("invalid endianness".to_string()))?;
+            let records = parse_tlv(
+                &page.to_bytes(),
+                page.offset,
+                type_width,
+                length_width,
+                endian,
+            );
+            let boundaries: Vec<u64> = records.iter().map(|tlv| tlv.offset).collect();
+            for tlv in records.iter() {
+                options
+                    .annotations
+                    .entry(tlv.offset)
+                    .or_default()
+                    .push(tlv_note(tlv));
+            }
+            page = split_at(page, &boundaries, options.column_width);
+        }
+        if let Some(layout) = matches.value_of("struct") {
+            let fields = parse_layout(layout)
+                .ok_or_else(|| Error::Application(format!("invalid struct layout '{}'", layout)))?;
+            let endian = parse_endian(matches.value_of("endian").unwrap_or("little"))
+                .ok_or_else(|| Error::Application("invalid endianness".to_string()))?;
+            let decoded = decode_struct(&page.to_bytes(), page.offset, &fields, endian);
+            let boundaries: Vec<u64> = decoded.iter().map(|(at, _)| *at).collect();
+            for (at, note) in decoded {
+                options.annotations.entry(at).or_default().push(note);
+            }
+            page = split_at(page, &boundaries, options.column_width);
+        }
+        // the passes above find offsets in file order, sort last
+        if matches.is_present("sort-lines") {
+            sort_lines(&mut page);
+        }
+        if matches.is_present("crc-delta") {
+            options.crc_deltas = crc_deltas(&page);
+        }
+        if matches.is_present("markdown") {
+            let caption = Path::new(name)
+                .file_name()
+                .map_or(name.into(), |name| name.to_string_lossy());
+            print_markdown(
+                &page,
+                &options,
+                &caption,
+                matches.value_of("markdown-lang"),
+                w,
+            )?;
+        } else if matches.is_present("diff") {
+            let context = parse_u64_arg(matches, "context")?.unwrap_or(3) as usize;
+            print_hunks(&page, &options, context, w)?;
+        } else if matches.is_present("grid") {
+            print_grid(&page, &options, unicode_locale(), w)?;
+        } else if let Some(lines) = parse_u64_arg(matches, "print-page")? {
+            print_paginated(&page, &options, lines as usize, name, w)?;
+        } else {
+            print_page(&page, &options, w)?;
+        }
+        if matches.is_present("class-summary") {
+            print_class_summary(&class_counts(&page.to_bytes()), w)?;
+        }
+    }
+    Ok(())
+}
+
+/// Bytes of `r` with their offsets, for consumers processing an input
+/// byte by byte without any formatting. Read errors are yielded as `Err`.
+///
+/// # Arguments
+///
+/// * `r` - Input, already positioned at `skip`.
+/// * `skip` - Offset of the first byte read.
+pub fn bytes_with_offsets<R: Read>(r: R, skip: u64) -> impl Iterator<Item = Result<(u64, u8)>> {
+    BufReader::new(r)
+        .bytes()
+        .enumerate()
+        .map(move |(i, b)| b.map(|b| (skip + i as u64, b)).map_err(Error::Io))
+}
+
+/// Buffer to array.
+///
+/// (https://rustbyexample.com/primitives/array.html)
+/// (https://stackoverflow.com/questions/39464237/whats-the-idiomatic-way-reference-bufreader-bufwriter-when-passing-between-funct)
+/// (https://stackoverflow.com/questions/39935158/bufreader-move-after-for-loop-with-bufreader-lines)
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `buf_len` - Buffer length.
+/// * `column_width` - column width for output.
+pub fn buf_to_array<R: BufRead>(buf: &mut R, buf_len: u64, column_width: u64) -> Result<Page> {
+    buf_to_page(buf, 0x0, buf_len, column_width)
+}
+
+/// Largest input held in a page, larger dumps would exhaust memory.
+pub const MAX_PAGE_LEN: u64 = 256 << 20;
+
+/// Buffer to page, line offsets starting at `base` (e.g. after a seek).
+/// Fails if `buf_len` exceeds `MAX_PAGE_LEN`.
+///
+/// # Arguments
+///
+/// * `buf` - Buffer to be read.
+/// * `base` - Offset of the first byte read.
+/// * `buf_len` - Buffer length.
+/// * `column_width` - column width for output.
+pub fn buf_to_page<R: BufRead>(
+    buf: &mut R,
+    base: u64,
     buf_len: u64,
     column_width: u64,
 ) -> Result<Page> {
     let mut column_count: u64 = 0x0;
-    let max_array_size: u16 = <u16>::max_value(); // 2^16;
+    let max_array_size: u16 = u16::MAX; // 2^16;
     let mut page: Page = Page::new();
+    page.offset = base;
     let mut line: Line = Line::new();
-    for b in buf.bytes() {
-        let b1: u8 = b.unwrap();
+    line.offset = base;
+    if buf_len == 0 {
+        return Ok(page);
+    }
+    if buf_len > MAX_PAGE_LEN {
+        return Err(Error::Application(format!(
+            "{} bytes exceed the {}MB in-memory limit, dump a part with --len",
+            buf_len,
+            MAX_PAGE_LEN >> 20
+        )));
+    }
+    for b in buf.by_ref().bytes() {
+        let b1: u8 = b?;
         line.bytes += 1;
         page.bytes += 1;
         line.hex_body.push(b1);
         column_count += 1;
 
-        if column_count >= column_width {
-            page.body.push(line);
-            line = Line::new();
-            column_count = 0;
+        if column_count >= column_width {
+            page.body.push(line);
+            line = Line::new();
+            line.offset = base + page.bytes;
+            column_count = 0;
+        }
+        if page.bytes == buf_len || max_array_size as u64 == buf_len {
+            break;
+        }
+    }
+    if line.bytes > 0 {
+        page.body.push(line);
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use std::io::Cursor;
+    /// @see (https://users.rust-lang.org/t/how-to-test-output-to-stdout/4877/6)
+    /// @see (https://rustbyexample.com/hello/print/print_display.html)
+    #[test]
+    fn test_offset() {
+        let b: u64 = 0x6;
+        assert_eq!(offset(b), "0x000006");
+        assert_eq!(offset(b), format!("{:#08x}", b));
+    }
+
+    /// hex octal, takes u8
+    #[test]
+    pub fn test_hex_octal() {
+        let b: u8 = 0x6;
+        assert_eq!(hex_octal(b), "0o0006");
+        assert_eq!(hex_octal(b), format!("{:#06o}", b));
+    }
+
+    /// hex lower hex, takes u8
+    #[test]
+    fn test_hex_lower_hex() {
+        let b: u8 = u8::MAX; // 255
+        assert_eq!(hex_lower_hex(b), "0xff");
+        assert_eq!(hex_lower_hex(b), format!("{:#04x}", b));
+    }
+
+    /// hex upper hex, takes u8
+    #[test]
+    fn test_hex_upper_hex() {
+        let b: u8 = u8::MAX;
+        assert_eq!(hex_upper_hex(b), "0xFF");
+        assert_eq!(hex_upper_hex(b), format!("{:#04X}", b));
+    }
+
+    /// hex binary, takes u8
+    #[test]
+    fn test_hex_binary() {
+        let b: u8 = u8::MAX;
+        assert_eq!(hex_binary(b), "0b11111111");
+        assert_eq!(hex_binary(b), format!("{:#010b}", b));
+    }
+
+    /// record labels are printed before each record
+    #[test]
+    fn test_record_label() {
+        let mut buf = Cursor::new(vec![0x61u8; 10]);
+        let page = buf_to_array(&mut buf, 10, 16).unwrap();
+        let page = split_records(page, 4, 16);
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 16;
+        options.record_size = Some(4);
+        options.record_label = Some("r{n}".to_string());
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "r0");
+        assert!(lines[1].starts_with("0x000000: "));
+        assert_eq!(lines[2], "r1");
+        assert!(lines[3].starts_with("0x000004: "));
+        assert_eq!(lines[4], "r2");
+        assert!(lines[5].starts_with("0x000008: "));
+        assert_eq!(
+            record_label("rec {n} @ {offset}", 1, &offset(4)),
+            "rec 1 @ 0x000004"
+        );
+
+        // the label offset agrees with the offset column
+        options.record_label = Some("r{n}@{offset}".to_string());
+        options.offset_format = OffsetFormat::Decimal;
+        options.addr_stride = 2;
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[2], "r1@00000008");
+        assert!(lines[3].starts_with("00000008: "));
+    }
+
+    /// text heuristic for --auto
+    #[test]
+    fn test_detect_text() {
+        let mut text: Vec<u8> = b"fn main() {\n    println!(\"h\xc3\xa9llo\");\n}\n".to_vec();
+        text.push(0x00);
+        assert!(detect_text(&text));
+        assert!(detect_text(&[0xef, 0xbb, 0xbf, 0x00, 0x01]));
+
+        let mut seed: u32 = 0x2545_f491;
+        let random: Vec<u8> = (0..1024)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        assert!(!detect_text(&random));
+
+        // a text range followed by random bytes is text
+        let mut input = vec![b'a'; 50];
+        input.extend_from_slice(&random);
+        let out = dump_args(&["-t", "0", "--auto", "--len", "50"], input).unwrap();
+        assert!(out
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with(&format!("{}: ", offset(16))));
+        assert_eq!(utf8_gutter(b"h\xc3\xa9\x00"), "hé.");
+        assert_eq!(ascii_gutter(b"h\xc3\xa9\x00"), "h...");
+    }
+
+    /// markdown fences surround the dump, without colors
+    #[test]
+    fn test_print_markdown() {
+        let mut buf = Cursor::new(b"il\n".to_vec());
+        let page = buf_to_array(&mut buf, 3, 16).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_markdown(&page, &Options::new(), "tiny.txt", Some("text"), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "`tiny.txt`");
+        assert_eq!(lines[2], "```text");
+        assert!(lines[3].starts_with("0x000000: 0x69 0x6c 0x0a "));
+        assert_eq!(lines[lines.len() - 1], "```");
+        assert!(!out.contains('\x1b'));
+    }
+
+    /// a high value region shows as a brighter minimap bucket
+    #[test]
+    fn test_minimap() {
+        let mut data: Vec<u8> = vec![0x00; 64];
+        data.extend(vec![0xff; 64]);
+        data.extend(vec![0x10; 64]);
+        let buckets = minimap_buckets(&mut Cursor::new(data), 192, 3).unwrap();
+        assert_eq!(buckets, vec![0x00, 0xff, 0x10]);
+        assert!(buckets[1] > buckets[0] && buckets[1] > buckets[2]);
+        assert_eq!(minimap_cell(buckets[1], false), "█");
+        assert_eq!(minimap_cell(buckets[0], false), " ");
+        assert!(minimap_cell(buckets[1], true).contains("38;5;255"));
+        assert!(minimap_cell(buckets[0], true).contains("38;5;232"));
+    }
+
+    /// hex text input
+    #[test]
+    fn test_parse_hex_input() {
+        let expected = Some(vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(parse_hex_input("de ad be ef"), expected);
+        assert_eq!(parse_hex_input("0xde, 0xad, 0xbe, 0xef\n"), expected);
+        assert_eq!(parse_hex_input("DEADBEEF"), expected);
+        assert_eq!(parse_hex_input("de:ad:be:ef"), expected);
+        assert_eq!(parse_hex_input("hello"), None);
+        assert_eq!(parse_hex_input("abc"), None);
+        assert_eq!(parse_hex_input(""), None);
+    }
+
+    /// clipboard returning a fixed text
+    #[cfg(feature = "clipboard")]
+    struct MockClipboard(&'static str);
+
+    #[cfg(feature = "clipboard")]
+    impl Clipboard for MockClipboard {
+        fn text(&mut self) -> Result<String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    /// clipboard hex text is decoded, anything else is dumped as is
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn test_clipboard_bytes() {
+        let bytes = clipboard_bytes(&mut MockClipboard("69 6c 0a")).unwrap();
+        assert_eq!(bytes, b"il\n".to_vec());
+        let bytes = clipboard_bytes(&mut MockClipboard("il")).unwrap();
+        assert_eq!(bytes, b"il".to_vec());
+    }
+
+    /// every other line carries the stripe background, only when colorizing
+    #[test]
+    fn test_zebra() {
+        let background = ansi_term::Style::new()
+            .on(ZEBRA_BACKGROUND)
+            .prefix()
+            .to_string();
+        let mut options = Options::new();
+        options.zebra = true;
+        let mut buf = Cursor::new((0..40).collect::<Vec<u8>>());
+        let page = buf_to_array(&mut buf, 40, 10).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let striped: Vec<bool> = out
+            .lines()
+            .take(4)
+            .map(|l| l.starts_with(&background))
+            .collect();
+        assert_eq!(striped, vec![false, true, false, true]);
+        // byte colors are kept on striped lines
+        let second = out.lines().nth(1).unwrap();
+        assert!(second.contains(
+            &byte_style(10)
+                .on(ZEBRA_BACKGROUND)
+                .paint("0x0a")
+                .to_string()
+        ));
+
+        options.colorize = false;
+        let mut plain: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut plain).unwrap();
+        assert!(!String::from_utf8(plain).unwrap().contains('\x1b'));
+    }
+
+    /// an oversized declared length is refused before reading
+    #[test]
+    fn test_max_page_len() {
+        let mut buf = Cursor::new(vec![0u8; 16]);
+        match buf_to_array(&mut buf, MAX_PAGE_LEN + 1, 10) {
+            Err(Error::Application(message)) => assert!(message.contains("--len")),
+            _ => panic!("oversized length accepted"),
+        }
+        let mut buf = Cursor::new(vec![0u8; 16]);
+        assert_eq!(buf_to_array(&mut buf, MAX_PAGE_LEN, 10).unwrap().bytes, 16);
+    }
+
+    /// offsets and octets are formatted independently
+    #[test]
+    fn test_offset_format() {
+        assert_eq!(format_offset(8, OffsetFormat::Hex, true), "0x000008");
+        assert_eq!(format_offset(8, OffsetFormat::Octal, true), "0o000010");
+        assert_eq!(format_offset(8, OffsetFormat::Decimal, false), "000008");
+
+        let mut options = Options::new();
+        options.colorize = false;
+        options.offset_format = OffsetFormat::Decimal;
+        options.format = parse_format("o");
+        let mut buf = Cursor::new((0..12).collect::<Vec<u8>>());
+        let page = buf_to_array(&mut buf, 12, 10).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("00000000: 0o0000 0o0001 "));
+        assert!(lines[1].starts_with("00000010: 0o0012 0o0013 "));
+    }
+
+    /// the counter sees every byte read, stats report them with a throughput
+    #[test]
+    fn test_stats() {
+        let mut buf = Counter::new(Cursor::new(vec![0x41u8; 64]));
+        let page = buf_to_array(&mut buf, 64, 10).unwrap();
+        assert_eq!(page.bytes, 64);
+        assert_eq!(buf.count, 64);
+
+        let mut out: Vec<u8> = Vec::new();
+        print_stats(buf.count, Duration::from_millis(2), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "stats: 64 bytes in 0.002000 s, 0.03 MB/s\n"
+        );
+    }
+
+    /// a leading bom is dropped from raw output only when stripping
+    #[test]
+    fn test_strip_bom() {
+        let data = b"\xef\xbb\xbfhi".to_vec();
+        let mut buf = Cursor::new(data.clone());
+        let mut out: Vec<u8> = Vec::new();
+        assert_eq!(strip_bom(&mut buf, 5).unwrap(), 3);
+        extract_bytes(&mut buf, 2, 0x00, u8::MAX, &mut out).unwrap();
+        assert_eq!(out, b"hi".to_vec());
+
+        let mut buf = Cursor::new(data.clone());
+        let mut out: Vec<u8> = Vec::new();
+        extract_bytes(&mut buf, 5, 0x00, u8::MAX, &mut out).unwrap();
+        assert_eq!(out, data);
+
+        let mut buf = Cursor::new(b"hi".to_vec());
+        assert_eq!(strip_bom(&mut buf, 2).unwrap(), 0);
+    }
+
+    /// only bytes within the value range are extracted
+    #[test]
+    fn test_extract_bytes() {
+        assert_eq!(parse_value_range("0x20:0x7e"), Some((0x20, 0x7e)));
+        assert_eq!(parse_value_range("0x7e:0x20"), None);
+        assert_eq!(parse_value_range("0:256"), None);
+
+        let data = b"\x00he\xffllo\n!".to_vec();
+        let mut buf = Cursor::new(data);
+        let mut out: Vec<u8> = Vec::new();
+        assert_eq!(extract_bytes(&mut buf, 9, 0x20, 0x7e, &mut out).unwrap(), 6);
+        assert_eq!(out, b"hello!".to_vec());
+    }
+
+    /// only lines differing from the baseline get the change bar
+    #[test]
+    fn test_baseline() {
+        let data: Vec<u8> = (0..30).collect();
+        let mut baseline = data.clone();
+        baseline[14] = 0xff;
+        let mut options = Options::new();
+        options.colorize = false;
+        options.baseline = Some(baseline);
+
+        let mut buf = Cursor::new(data);
+        let page = buf_to_array(&mut buf, 30, 10).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let bars: Vec<bool> = out.lines().take(3).map(|l| l.starts_with("| ")).collect();
+        assert_eq!(bars, vec![false, true, false]);
+        assert!(out
+            .lines()
+            .next()
+            .unwrap()
+            .starts_with(&format!("  {}", offset(0))));
+    }
+
+    /// compression is detected by extension first, then by magic
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(
+            detect_compression(Path::new("dump.bin.gz"), b""),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            detect_compression(Path::new("dump.xz"), &[0x1f, 0x8b]),
+            Some(Compression::Xz)
+        );
+        assert_eq!(
+            detect_compression(Path::new("dump"), &[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            detect_compression(Path::new("dump"), b"BZh91AY"),
+            Some(Compression::Bzip2)
+        );
+        assert_eq!(detect_compression(Path::new("dump.txt"), b"text"), None);
+    }
+
+    /// a gzip input dumps like its raw content
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_input() {
+        let raw: Vec<u8> = (0..100).collect();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&raw).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let compression = detect_compression(Path::new("input"), &compressed).unwrap();
+        let decompressed = decompress(Cursor::new(compressed), compression).unwrap();
+
+        let mut options = Options::new();
+        options.colorize = false;
+        let mut expected: Vec<u8> = Vec::new();
+        let page = buf_to_array(&mut Cursor::new(raw), 100, 10).unwrap();
+        print_page(&page, &options, &mut expected).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        let page = buf_to_array(&mut Cursor::new(decompressed), 100, 10).unwrap();
+        print_page(&page, &options, &mut out).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    /// a named section is found at its file offset, unknown ones list the others
+    #[cfg(feature = "sections")]
+    #[test]
+    fn test_find_section() {
+        let elf = fs::read("tests/files/tiny.o").unwrap();
+        let (start, size) = find_section(&elf, ".data").unwrap();
+        assert_eq!(size, 4);
+        assert_eq!(
+            &elf[start as usize..(start + size) as usize],
+            &42u32.to_le_bytes()
+        );
+
+        match find_section(&elf, ".nope") {
+            Err(Error::Application(message)) => {
+                assert!(message.contains(".nope"));
+                assert!(message.contains(".data"));
+            }
+            _ => panic!("unknown section found"),
+        }
+        assert!(find_section(b"plain text", ".data").is_err());
+    }
+
+    /// offsets count from the skip value, read errors come through as Err
+    #[test]
+    fn test_bytes_with_offsets() {
+        let bytes: Vec<(u64, u8)> = bytes_with_offsets(Cursor::new(b"abc".to_vec()), 0x10)
+            .map(|item| item.unwrap())
+            .collect();
+        assert_eq!(bytes, vec![(0x10, b'a'), (0x11, b'b'), (0x12, b'c')]);
+
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("device gone"))
+            }
+        }
+        let mut failing = bytes_with_offsets(Failing, 0);
+        match failing.next() {
+            Some(Err(Error::Io(e))) => assert_eq!(e.to_string(), "device gone"),
+            _ => panic!("read error swallowed"),
+        }
+    }
+
+    /// an aligned dump starts on the boundary below the skip offset
+    #[test]
+    fn test_align() {
+        let data: Vec<u8> = (0..0x40).collect();
+        let mut buf = Cursor::new(data);
+        buf.seek(SeekFrom::Start(0x13)).unwrap();
+        let page = buf_to_page(&mut buf, 0x13, 0x2d, 16).unwrap();
+        let page = align_page(page, 16, 16);
+        assert_eq!(page.body[0].offset, 0x13);
+        assert_eq!(page.body[0].lead, 3);
+        assert_eq!(page.body[0].bytes, 13);
+        assert_eq!(page.body[1].offset, 0x20);
+
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 16;
+        let mut out: Vec<u8> = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        let first = String::from_utf8(out).unwrap();
+        let placeholders = " ".repeat(3 * 5);
+        assert!(first.starts_with(&format!("{}: {}0x13 ", offset(0x10), placeholders)));
+        let mut out: Vec<u8> = Vec::new();
+        print_line(&page.body[1], 1, &options, &mut out).unwrap();
+        let second = String::from_utf8(out).unwrap();
+        assert_eq!(first.find("0x13"), second.find("0x23"));
+    }
+
+    /// failed checks and io errors exit with distinct codes
+    #[test]
+    fn test_exit_code() {
+        let mismatch = Error::Mismatch("byte at 0x000000 is 0x00, expected 0xff".to_string());
+        assert_eq!(mismatch.exit_code(), EXIT_MISMATCH);
+
+        let missing = File::open("/nonexistent/hex-input").map_err(Error::from);
+        assert_eq!(missing.unwrap_err().exit_code(), EXIT_IO);
+
+        assert_eq!(
+            Error::Application("invalid range".to_string()).exit_code(),
+            EXIT_ERROR
+        );
+        assert_eq!(
+            Error::NotFound("pattern".to_string()).exit_code(),
+            EXIT_NOT_FOUND
+        );
+
+        // a failed content check and a missing input, through a whole run
+        let path = |name: &str| {
+            let path = std::env::temp_dir().join(format!("hex-{}-{}", name, std::process::id()));
+            path.to_string_lossy().to_string()
+        };
+        let (input, output) = (path("exit-in"), path("exit-out"));
+        fs::write(&input, [0x00, 0x00, 0x01]).unwrap();
+        let run_line = |args: Vec<&str>| run(app().get_matches_from(args));
+        let checked = run_line(vec![
+            "hex",
+            "--expect-fill",
+            "0",
+            "--output",
+            &output,
+            &input,
+        ]);
+        assert_eq!(checked.unwrap_err().exit_code(), EXIT_MISMATCH);
+        let passed = run_line(vec![
+            "hex",
+            "--expect-fill",
+            "0",
+            "--len",
+            "2",
+            "--output",
+            &output,
+            &input,
+        ]);
+        assert!(passed.is_ok());
+        let missing = run_line(vec!["hex", "--output", &output, "/nonexistent/hex-input"]);
+        assert_eq!(missing.unwrap_err().exit_code(), EXIT_IO);
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+    }
+
+    /// utf-16le strings are found at their offset, short runs dropped
+    #[test]
+    fn test_utf16_strings() {
+        let mut data: Vec<u8> = vec![0xff, 0x4d, 0x5a];
+        for c in "kernel32".bytes() {
+            data.extend_from_slice(&[c, 0x00]);
+        }
+        data.extend_from_slice(&[0x00, 0x00, b'a', 0x00, 0x01]);
+        let len = data.len() as u64;
+        let mut buf = Cursor::new(data.clone());
+        assert_eq!(
+            utf16_strings(&mut buf, 0x100, len, 4).unwrap(),
+            vec![(0x103, "kernel32".to_string())]
+        );
+        let mut buf = Cursor::new(data);
+        assert_eq!(utf16_strings(&mut buf, 0x100, len, 1).unwrap().len(), 2);
+    }
+
+    /// marked bytes take the accent color, the others their palette color
+    #[test]
+    fn test_accent() {
+        assert_eq!(parse_color_mode("never", true), Some(false));
+        assert_eq!(parse_color_mode("auto", false), Some(false));
+        assert_eq!(parse_color_mode("auto", true), Some(true));
+        assert_eq!(parse_color_name("chartreuse"), None);
+
+        let data = b"xxabxab".to_vec();
+        let marks = find_marks(&data, 0, b"ab");
+        assert_eq!(marks, vec![(2, 4), (5, 7)]);
+        assert_eq!(find_marks(b"aaaa", 0, b"aa"), vec![(0, 4)]);
+
+        let mut options = Options::new();
+        options.accent = parse_color_name("green").unwrap();
+        options.marks = marks;
+        let page = buf_to_array(&mut Cursor::new(data), 7, 10).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let green = |b: &str| {
+            ansi_term::Color::Green
+                .bold()
+                .paint(b.to_string())
+                .to_string()
+        };
+        assert!(out.contains(&green("0x61")));
+        assert!(out.contains(&green("0x62")));
+        assert!(!out.contains(&green("0x78")));
+        assert!(out.contains(&byte_style(0x78).paint("0x78").to_string()));
+
+        // the same through a dump searching for the marked bytes
+        let out = dump_args(
+            &["-t", "always", "--mark", "61 62", "--accent", "green"],
+            b"xxabxab".to_vec(),
+        )
+        .unwrap();
+        assert!(out.contains(&green("0x61")));
+        assert!(out.contains(&green("0x62")));
+        assert!(!out.contains(&green("0x78")));
+    }
+
+    /// plain and oneline octets are joined with the separator string
+    #[test]
+    fn test_sep_str() {
+        let page = buf_to_array(&mut Cursor::new(vec![0xde, 0xad, 0xbe, 0xef]), 4, 2).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_oneline(&page, ":", false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "de:ad:be:ef\n");
+
+        let mut out: Vec<u8> = Vec::new();
+        print_oneline(&page, "", false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "deadbeef\n");
+
+        let mut out: Vec<u8> = Vec::new();
+        print_plain(&page, "-", false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "de-ad\nbe-ef\n");
+    }
+
+    /// array elements changed from the baseline are highlighted
+    #[test]
+    fn test_array_baseline() {
+        let page = buf_to_array(&mut Cursor::new(vec![0x01, 0x02, 0x03]), 3, 10).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_array(
+            &page,
+            "r",
+            Some(&[0x01, 0xff, 0x03]),
+            false,
+            false,
+            &mut out,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let changed = change_style().paint("0x02").to_string();
+        assert!(out.contains(&format!("0x01, {}, 0x03", changed)));
+
+        let mut plain: Vec<u8> = Vec::new();
+        print_array(&page, "r", None, false, false, &mut plain).unwrap();
+        assert_eq!(
+            String::from_utf8(plain).unwrap(),
+            "let ARRAY: [u8; 3] = [\n    0x01, 0x02, 0x03\n];\n"
+        );
+    }
+
+    /// the dump stops at the line completing the marker
+    #[test]
+    fn test_until_ascii() {
+        let data = b"header....payload.ENDtrailer...".to_vec();
+        let page = buf_to_array(&mut Cursor::new(data.clone()), 31, 10).unwrap();
+        let page = until_ascii(page, "END");
+        assert_eq!(page.body.len(), 3);
+        assert_eq!(page.bytes, 30);
+        assert!(ascii_gutter(&page.body[2].hex_body).starts_with("Dtrailer"));
+
+        let page = buf_to_array(&mut Cursor::new(data), 31, 10).unwrap();
+        assert_eq!(until_ascii(page, "nowhere").body.len(), 4);
+    }
+
+    /// repetitions continue the offsets of the previous ones
+    #[test]
+    fn test_repeat_page() {
+        let page = buf_to_array(&mut Cursor::new(b"abcd".to_vec()), 4, 10).unwrap();
+        let page = repeat_page(page, 2, 10).unwrap();
+        assert_eq!(page.bytes, 8);
+        assert_eq!(page.body.len(), 1);
+        assert_eq!(page.body[0].offset, 0);
+        assert_eq!(page.body[0].hex_body, b"abcdabcd".to_vec());
+
+        let page = buf_to_array(&mut Cursor::new(b"abcd".to_vec()), 4, 3).unwrap();
+        let offsets: Vec<u64> = repeat_page(page, 2, 3)
+            .unwrap()
+            .body
+            .iter()
+            .map(|line| line.offset)
+            .collect();
+        assert_eq!(offsets, vec![0, 3, 6]);
+    }
+
+    /// every byte is counted in exactly one class
+    #[test]
+    fn test_class_counts() {
+        let counts = class_counts(b"ab c\t\n\x00\x00\x01\x7f\x80\xff!");
+        assert_eq!(
+            counts,
+            ClassCounts {
+                printable: 4,
+                whitespace: 3,
+                control: 2,
+                high: 2,
+                null: 2,
+            }
+        );
+        let mut out: Vec<u8> = Vec::new();
+        print_class_summary(&counts, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "   printable: 4, whitespace: 3, control: 2, high: 2, null: 2\n"
+        );
+    }
+
+    /// the heatmap has one pixel per byte, rows of the column width
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_write_heatmap() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut image: Vec<u8> = Vec::new();
+        assert_eq!(write_heatmap(&bytes, 10, &mut image).unwrap(), (10, 26));
+
+        let decoder = png::Decoder::new(Cursor::new(image));
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (10, 26));
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+    }
+
+    /// constant record positions have a single value, varying ones several
+    #[test]
+    fn test_column_summaries() {
+        let records = vec![0xaa, 0x01, 0xaa, 0x02, 0xaa, 0x03, 0xaa];
+        let summaries = column_summaries(&mut Cursor::new(records), 7, 2).unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].distinct, 1);
+        assert_eq!(summaries[0].first, 0xaa);
+        assert_eq!(summaries[0].hash, crc32(&[0xaa; 4]));
+        assert_eq!(summaries[1].distinct, 3);
+        assert_eq!(summaries[1].hash, crc32(&[0x01, 0x02, 0x03]));
+
+        let summaries = column_summaries(&mut Cursor::new(vec![0xaa, 0xbb]), 2, u64::MAX).unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[1].first, 0xbb);
+    }
+
+    /// uuids in rfc and microsoft byte order
+    #[test]
+    fn test_format_uuid() {
+        let bytes = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        assert_eq!(
+            format_uuid(&bytes, false),
+            "00112233-4455-6677-8899-aabbccddeeff"
+        );
+        assert_eq!(
+            format_uuid(&bytes, true),
+            "33221100-5544-7766-8899-aabbccddeeff"
+        );
+    }
+
+    /// hyperlinked offsets are wrapped in osc 8 sequences
+    #[test]
+    fn test_hyperlink_offsets() {
+        let mut options = Options::new();
+        options.colorize = false;
+        options.hyperlink = Some("file:///tmp/a.bin#{offset}".to_string());
+        let page =
+            buf_to_page(&mut Cursor::new(vec![0x41]), 0x20, 1, options.column_width).unwrap();
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("\x1b]8;;file:///tmp/a.bin#0x20\x1b\\0x000020\x1b]8;;\x1b\\: "));
+    }
+
+    /// only files dumped to the terminal link to the input by default
+    #[test]
+    fn test_hyperlink_template() {
+        let path = std::env::temp_dir().join(format!("hex-hyperlink-{}", std::process::id()));
+        fs::write(&path, b"a").unwrap();
+        let file = path.to_string_lossy().to_string();
+        let matches = app().get_matches_from(vec!["hex", "--hyperlinks", &file]);
+        let template = hyperlink_template(&matches, true).unwrap();
+        assert!(template.starts_with("file:///") && template.ends_with("#{offset}"));
+        assert_eq!(hyperlink_template(&matches, false), None);
+        let stdin = app().get_matches_from(vec!["hex", "--hyperlinks", "-"]);
+        assert_eq!(hyperlink_template(&stdin, true), None);
+        let command = app().get_matches_from(vec!["hex", "--hyperlinks", "--cmd", "true"]);
+        assert_eq!(hyperlink_template(&command, true), None);
+        let _ = fs::remove_file(&path);
+    }
+
+    /// compact binary rows are bare bits separated by single spaces
+    #[test]
+    fn test_binary_compact_line() {
+        let mut options = Options::new();
+        options.colorize = false;
+        options.format = Format::Binary;
+        options.compact = true;
+        let page = buf_to_page(&mut Cursor::new(vec![0xff, 0x00]), 0, 2, 2).unwrap();
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("000000 11111111 00000000 "));
+    }
+
+    /// a stream is read up to its announced total, and must reach it
+    #[test]
+    fn test_read_stream_total_size() {
+        let input = b"0123456789".to_vec();
+        assert_eq!(
+            read_stream(Cursor::new(input.clone()), Some(4)).unwrap(),
+            b"0123"
+        );
+        assert_eq!(
+            read_stream(Cursor::new(input.clone()), None).unwrap(),
+            input
+        );
+        assert_eq!(
+            read_stream(Cursor::new(input), Some(16))
+                .unwrap_err()
+                .exit_code(),
+            EXIT_MISMATCH
+        );
+    }
+
+    /// runs of identical bytes long enough are reported with their length
+    #[test]
+    fn test_byte_runs() {
+        let mut bytes = vec![0x01, 0x02, 0x02];
+        bytes.extend_from_slice(&[0xcc; 8]);
+        bytes.push(0x03);
+        let runs = byte_runs(&bytes, 0x100, 4);
+        assert_eq!(runs, vec![(0x103, 0xcc, 8)]);
+        assert_eq!(
+            format!("{}\u{d7}{}", hex_lower_hex(runs[0].1), runs[0].2),
+            "0xcc\u{d7}8"
+        );
+        assert_eq!(byte_runs(&bytes, 0, 2).len(), 2);
+    }
+
+    /// every line gets the prefix, the rest of the line is untouched
+    #[test]
+    fn test_prefixed_lines() {
+        let mut options = Options::new();
+        options.colorize = false;
+        let page = buf_to_page(&mut Cursor::new(b"abcdefghijkl".to_vec()), 0, 12, 10).unwrap();
+        let mut plain = Vec::new();
+        print_page(&page, &options, &mut plain).unwrap();
+        let mut out = Prefixed::new(Vec::new(), "    ");
+        print_page(&page, &options, &mut out).unwrap();
+        let prefixed = String::from_utf8(out.inner).unwrap();
+        let plain = String::from_utf8(plain).unwrap();
+        assert_eq!(prefixed.lines().count(), plain.lines().count());
+        for (prefixed, plain) in prefixed.lines().zip(plain.lines()) {
+            assert_eq!(prefixed, format!("    {}", plain));
+        }
+    }
+
+    /// fixed-point values are scaled by their fractional bits, sign included
+    #[test]
+    fn test_read_fixed() {
+        assert_eq!(parse_fixed("Q8.8"), Some((8, 8)));
+        assert_eq!(parse_fixed("Q3.4"), None);
+        assert_eq!(read_fixed(&[0x01, 0x80], 8, Endian::Big), 1.5);
+        assert_eq!(read_fixed(&[0x80, 0x01], 8, Endian::Little), 1.5);
+        assert_eq!(read_fixed(&[0xfe, 0x80], 8, Endian::Big), -1.5);
+        assert_eq!(
+            format!("{:.*}", 4, read_fixed(&[0x01, 0x80], 8, Endian::Big)),
+            "1.5000"
+        );
+
+        // trailing bytes short of a value are shown raw
+        let out = dump_args(
+            &["--fixed", "Q8.8", "--endian", "big"],
+            vec![0x01, 0x80, 0xfe, 0x80, 0x02],
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "0x000000: 1.5000\n0x000002: -1.5000\n0x000004: 0x02 (incomplete value)\n"
+        );
+    }
+
+    /// the final newline is dropped on request, inner newlines are kept
+    #[test]
+    fn test_final_newline() {
+        let mut options = Options::new();
+        options.colorize = false;
+        let page = buf_to_page(&mut Cursor::new(b"abcdefghijkl".to_vec()), 0, 12, 10).unwrap();
+        let mut kept = FinalNewline::new(Vec::new(), false);
+        print_page(&page, &options, &mut kept).unwrap();
+        assert!(kept.inner.ends_with(b"\n"));
+        let mut omitted = FinalNewline::new(Vec::new(), true);
+        print_page(&page, &options, &mut omitted).unwrap();
+        assert!(!omitted.inner.ends_with(b"\n"));
+        assert_eq!(omitted.inner[..], kept.inner[..kept.inner.len() - 1]);
+    }
+
+    /// printable runs are quoted and the separators escaped
+    #[test]
+    fn test_quoted_gutter() {
+        assert_eq!(quoted_gutter(b"abc\x00de\x00"), "\"abc\"\\0\"de\"\\0");
+        assert_eq!(quoted_gutter(b"\x01a\"b"), "\\x01\"a\\\"b\"");
+        let mut options = Options::new();
+        options.colorize = false;
+        options.quote_strings = true;
+        let page = buf_to_page(&mut Cursor::new(b"abc\x00de\x00".to_vec()), 0, 7, 10).unwrap();
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with(" \"abc\"\\0\"de\"\\0\n"));
+    }
+
+    /// the offset column steps by the address stride per byte
+    #[test]
+    fn test_addr_stride() {
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 1;
+        options.addr_stride = 2;
+        let page = buf_to_page(&mut Cursor::new(vec![1, 2, 3]), 0, 3, 1).unwrap();
+        let mut out = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let labels: Vec<String> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .take(3)
+            .map(|line| line[..8].to_string())
+            .collect();
+        assert_eq!(labels, vec!["0x000000", "0x000002", "0x000004"]);
+    }
+
+    /// matching bytes are counted at identical offsets, a longer tail differs
+    #[test]
+    fn test_similarity() {
+        let a = Cursor::new(b"abcdefgh".to_vec());
+        let b = Cursor::new(b"abcdefXY".to_vec());
+        assert_eq!(similarity(a, b).unwrap(), (6, 8));
+        let a = Cursor::new(b"abc".to_vec());
+        let b = Cursor::new(b"abcd".to_vec());
+        assert_eq!(similarity(a, b).unwrap(), (3, 4));
+    }
+
+    /// only the dumped part of the other file is compared
+    #[test]
+    fn test_similarity_part() {
+        let other_path =
+            std::env::temp_dir().join(format!("hex-similarity-{}", std::process::id()));
+        let data: Vec<u8> = (0..64u8).collect();
+        fs::write(&other_path, &data).unwrap();
+        let other = other_path.to_string_lossy().to_string();
+        for args in [
+            &["--len", "16"][..],
+            &["--skip", "8", "--len", "16"][..],
+            &["--skip", "48"][..],
+        ] {
+            let mut compared = args.to_vec();
+            compared.extend_from_slice(&["--similarity", &other]);
+            let out = dump_args(&compared, data.clone()).unwrap();
+            assert!(
+                out.starts_with("similarity: 100.00%, 0 of "),
+                "{:?}: {}",
+                args,
+                out
+            );
+        }
+        let _ = fs::remove_file(&other_path);
+    }
+
+    /// a text half and a binary half fall in different classes
+    #[test]
+    fn test_distribution_map() {
+        let mut bytes = b"Lorem ipsum dolor sit amet, consectetur".to_vec();
+        bytes.truncate(32);
+        bytes.extend_from_slice(&[0xe9; 32]);
+        let map = distribution_map(&mut Cursor::new(bytes), 0x10, 64, 2).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[0].0, 0x10);
+        assert_eq!(map[0].1.dominant(), "printable");
+        assert_eq!(map[1].0, 0x30);
+        assert_eq!(map[1].1.dominant(), "high");
+    }
+
+    /// struct field assertions pass on the expected value only
+    #[test]
+    fn test_check_assertion() {
+        let fields = parse_layout("u32:magic,u8:class,4s:tag").unwrap();
+        let bytes = b"\x7fELF\x02core";
+        assert!(check_assertion(bytes, &fields, Endian::Big, "magic==0x7f454c46").is_ok());
+        assert!(check_assertion(bytes, &fields, Endian::Big, "class != 1").is_ok());
+        assert!(check_assertion(bytes, &fields, Endian::Big, "tag==\"core\"").is_ok());
+        let err = check_assertion(bytes, &fields, Endian::Big, "magic==0xcafebabe").unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_MISMATCH);
+        assert_eq!(
+            err.to_string(),
+            "Mismatch: assertion 'magic==0xcafebabe' failed: magic is 0x7f454c46"
+        );
+        assert_eq!(
+            check_assertion(bytes, &fields, Endian::Big, "size==1")
+                .unwrap_err()
+                .exit_code(),
+            EXIT_ERROR
+        );
+    }
+
+    /// 8-byte groups read as little-endian pointers, partial groups padded
+    #[test]
+    fn test_pointer_gutter() {
+        let bytes = [
+            0x78, 0x56, 0x34, 0x12, 0xff, 0x7f, 0x00, 0x00, 0x01, 0x02, 0x03,
+        ];
+        assert_eq!(pointer_gutter(&bytes[..8]), "0x00007fff12345678");
+        assert_eq!(
+            pointer_gutter(&bytes),
+            "0x00007fff12345678 0x??????????030201"
+        );
+    }
+
+    /// a length past the end of the input is clamped to the input length
+    #[test]
+    fn test_effective_len() {
+        let input = vec![0x61u8; 100];
+        let buf_len = effective_len(1_000_000, input.len() as u64);
+        assert_eq!(buf_len, 100);
+        assert_eq!(effective_len(10, 100), 10);
+        assert!(effective_len(1 << 40, 100) <= MAX_PAGE_LEN);
+        let page = buf_to_page(&mut Cursor::new(input), 0, buf_len, 10).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        let mut out = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("   bytes: 100\n"));
+        assert_eq!(out.lines().count(), 11);
+
+        // the summary reports the real length through a whole dump
+        let out = dump_args(&["-t", "0", "--len", "1000000"], vec![0x61u8; 100]).unwrap();
+        assert_eq!(out.lines().last(), Some("   bytes: 100"));
+        assert_eq!(out.lines().count(), 11);
+
+        // invalid lengths are errors, not panics
+        for args in [&["--len", "foo"][..], &["--cols", "foo"][..]] {
+            match dump_args(args, vec![0x61u8; 100]) {
+                Err(e) => assert_eq!(e.exit_code(), EXIT_ERROR),
+                Ok(_) => panic!("{:?} is invalid", args),
+            }
+        }
+    }
+
+    /// modeline settings apply to the options
+    #[test]
+    fn test_modeline() {
+        let settings = parse_modeline("# hex: cols=8 format=X\n").unwrap();
+        assert_eq!(
+            settings,
+            vec![
+                ("cols".to_string(), "8".to_string()),
+                ("format".to_string(), "X".to_string())
+            ]
+        );
+        let mut options = Options::new();
+        apply_modeline(&settings, &mut options).unwrap();
+        assert_eq!(options.column_width, 8);
+        assert_eq!(parse_modeline("just text"), None);
+        let settings = parse_modeline("# hex: rows=8").unwrap();
+        assert!(apply_modeline(&settings, &mut options).is_err());
+
+        options.colorize = false;
+        let input = b"# hex: cols=8\nabcdefghijklmnop".to_vec();
+        let page = buf_to_page(&mut Cursor::new(input), 0, 30, options.column_width).unwrap();
+        assert_eq!(page.body[0].bytes, 8);
+        assert_eq!(page.body.len(), 4);
+    }
+
+    /// delimited records start their own lines and offsets
+    #[test]
+    fn test_split_on_delimiter() {
+        let bytes = b"ab\ncde\n".to_vec();
+        let records = delimited_records(&bytes, 0x10, b'\n');
+        assert_eq!(records, vec![(0x10, 3), (0x13, 4)]);
+        let page = buf_to_page(&mut Cursor::new(bytes), 0x10, 7, 10).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.record_starts = records.iter().map(|(at, _)| *at).collect();
+        let page = split_at(page, &options.record_starts, options.column_width);
+        let hidden = drop_delimiters(page.clone(), b'\n');
+        assert_eq!(hidden.body[0].hex_body, b"ab");
+        assert_eq!(hidden.body[1].hex_body, b"cde");
+        let mut out = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("0x000000: 0x61 0x62 0x0a "));
+        assert!(lines[1].starts_with("0x000000: 0x63 0x64 0x65 0x0a "));
+    }
+
+    /// the dry-run report lists the effective settings
+    #[test]
+    fn test_print_settings() {
+        let mut options = Options::new();
+        options.column_width = 16;
+        let mut out = Vec::new();
+        print_settings("a.bin", 0x20, 64, &options, "stdout", &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "input: a.bin\nskip: 0x000020\nlen: 64\nformat: LowerHex\ncols: 16\noutput: stdout\n"
+        );
+    }
+
+    /// similar inputs have similar fuzzy hashes, unrelated ones do not
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn test_fuzzy_hash() {
+        let noise = |seed: u32, len: usize| -> Vec<u8> {
+            let mut state = seed;
+            (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (state >> 16) as u8
+                })
+                .collect()
+        };
+        let original = noise(1, 16384);
+        let mut edited = original.clone();
+        for b in edited[8000..8040].iter_mut() {
+            *b = 0;
+        }
+        let unrelated = noise(2, 16384);
+        let hash = fuzzy_hash(&original);
+        assert_eq!(fuzzy_compare(&hash, &hash), Some(100));
+        assert!(fuzzy_compare(&hash, &fuzzy_hash(&edited)).unwrap() > 50);
+        assert_eq!(fuzzy_compare(&hash, &fuzzy_hash(&unrelated)), Some(0));
+        assert_eq!(fuzzy_compare(&hash, "not a hash"), None);
+    }
+
+    /// lines of the two inputs alternate, labeled with their source
+    #[test]
+    fn test_print_interleaved() {
+        let first: Vec<u8> = (0..32).collect();
+        let mut second = first.clone();
+        second[20] = 0xff;
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 16;
+        let a = buf_to_page(&mut Cursor::new(first), 0, 32, 16).unwrap();
+        let b = buf_to_page(&mut Cursor::new(second), 0, 32, 16).unwrap();
+        let mut out = Vec::new();
+        print_interleaved(&a, &b, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("A 0x000000: 0x00 "));
+        assert!(lines[1].starts_with("B 0x000000: 0x00 "));
+        assert!(lines[2].starts_with("A 0x000010: 0x10 0x11 0x12 0x13 0x14 "));
+        assert!(lines[3].starts_with("B 0x000010: 0x10 0x11 0x12 0x13 0xff "));
+        assert_eq!(lines[4], "   bytes: A 32, B 32");
+    }
+
+    /// printed pages are separated by form feeds and numbered
+    #[test]
+    fn test_print_paginated() {
+        let page = buf_to_page(&mut Cursor::new(vec![0x61u8; 50]), 0, 50, 10).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        let mut out = Vec::new();
+        print_paginated(&page, &options, 2, "a.bin", &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let pages: Vec<&str> = out.split('\x0c').collect();
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].starts_with("a.bin, page 1 of 3\n\n0x000000: "));
+        assert!(pages[0].ends_with("\n-- 1 --\n"));
+        assert!(pages[1].starts_with("a.bin, page 2 of 3\n\n0x000014: "));
+        assert_eq!(pages[1].lines().count(), 6);
+        assert!(pages[2].starts_with("a.bin, page 3 of 3\n\n0x000028: "));
+        assert!(pages[2].ends_with("-- 3 --\n   bytes: 50\n"));
+    }
+
+    /// leb128 integers decode with their length
+    #[test]
+    fn test_decode_leb128() {
+        assert_eq!(
+            decode_leb128(&[0xe5, 0x8e, 0x26, 0xff]),
+            Some((624485, 624485, 3))
+        );
+        assert_eq!(decode_leb128(&[0x7f]), Some((127, -1, 1)));
+        assert_eq!(
+            decode_leb128(&[0xc0, 0xbb, 0x78]),
+            Some((1973696, -123456, 3))
+        );
+        assert_eq!(decode_leb128(&[0x80, 0x80]), None);
+    }
+
+    /// alternating byte groups alternate their tint
+    #[test]
+    fn test_alt_group_color() {
+        let page = buf_to_page(&mut Cursor::new(vec![0x41; 8]), 0, 8, 8).unwrap();
+        let mut options = Options::new();
+        options.column_width = 8;
+        options.alt_group = Some(2);
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let tints: Vec<&str> = out
+            .split("\x1b[38;5;")
+            .skip(1)
+            .map(|code| &code[..3])
+            .collect();
+        assert_eq!(
+            tints,
+            vec!["255", "255", "245", "245", "255", "255", "245", "245"]
+        );
+    }
+
+    /// a page gives back the bytes of its lines in order
+    #[test]
+    fn test_page_to_bytes() {
+        let mut page = Page::new();
+        for (offset, bytes) in [(0, vec![1, 2, 3]), (3, vec![4, 5])] {
+            let mut line = Line::new();
+            line.offset = offset;
+            line.bytes = bytes.len() as u64;
+            line.hex_body = bytes;
+            page.body.push(line);
+        }
+        page.bytes = 5;
+        assert_eq!(page.to_bytes(), vec![1, 2, 3, 4, 5]);
+    }
+
+    /// set bits are listed from the most significant
+    #[test]
+    fn test_set_bits() {
+        assert_eq!(set_bits(0x91), "[7 4 0]");
+        assert_eq!(set_bits(0x00), "[]");
+        let page = buf_to_page(&mut Cursor::new(vec![0x91, 0x02]), 0, 2, 4).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 4;
+        options.bitmask = true;
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x000000: 0x91 0x02           ..   [7 4 0] [1]\n"
+        );
+    }
+
+    /// identity tables keep the bytes, other tables substitute them
+    #[test]
+    fn test_map_table() {
+        let path = std::env::temp_dir().join(format!("hex-map-table-{}", std::process::id()));
+        let identity: Vec<u8> = (0..=255).collect();
+        fs::write(&path, &identity).unwrap();
+        let table = load_map_table(path.to_str().unwrap()).unwrap();
+        let mut bytes = b"abc".to_vec();
+        remap(&mut bytes, Some(&table));
+        assert_eq!(bytes, b"abc");
+
+        let mut swapped = identity.clone();
+        swapped.swap(b'a' as usize, b'b' as usize);
+        fs::write(&path, &swapped).unwrap();
+        let table = load_map_table(path.to_str().unwrap()).unwrap();
+        remap(&mut bytes, Some(&table));
+        assert_eq!(bytes, b"bac");
+
+        fs::write(&path, &swapped[..255]).unwrap();
+        assert!(load_map_table(path.to_str().unwrap()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// only the changed line and its context are printed, under a header
+    #[test]
+    fn test_print_hunks() {
+        let input: Vec<u8> = (0..100).collect();
+        let mut baseline = input.clone();
+        baseline[55] = 0xff;
+        let page = buf_to_page(&mut Cursor::new(input), 0, 100, 10).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.baseline = Some(baseline);
+        let mut out = Vec::new();
+        print_hunks(&page, &options, 1, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "@@ 0x000028..0x000046 @@");
+        assert!(lines[1].starts_with("  0x000028: "));
+        assert!(lines[2].starts_with("| 0x000032: "));
+        assert!(lines[3].starts_with("  0x00003c: "));
+        assert_eq!(lines[4], "   bytes: 100");
+    }
+
+    /// a repeated pattern is detected with its length, noise has no period
+    #[test]
+    fn test_detect_period() {
+        let bytes = b"\x13\x37\xc0\xde".repeat(64);
+        assert_eq!(detect_period(&bytes, 4096, 0.9), Some((4, 1.0)));
+        let mut noisy = bytes.clone();
+        noisy[100] = 0;
+        assert_eq!(detect_period(&noisy, 4096, 0.9).unwrap().0, 4);
+        let noise: Vec<u8> = (0..255u32).map(|i| (i * 7 % 251) as u8).collect();
+        assert_eq!(detect_period(&noise, 64, 0.9), None);
+    }
+
+    /// a custom styler decides the style of every byte
+    #[test]
+    fn test_dump_with_styler() {
+        let page = buf_to_page(&mut Cursor::new(vec![0x00, 0x01, 0x02]), 0, 3, 10).unwrap();
+        let mut out = Vec::new();
+        let styler = |at: u64, _: u8| {
+            if at.is_multiple_of(2) {
+                Some(ansi_term::Color::Red.normal())
+            } else {
+                None
+            }
+        };
+        dump_with_styler(&page, styler, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("0x000000: \x1b[31m0x00\x1b[0m 0x01 \x1b[31m0x02\x1b[0m "));
+        assert!(out.ends_with("   bytes: 3\n"));
+    }
+
+    /// the dump length ends before the first null byte
+    #[test]
+    fn test_len_to_null() {
+        let mut buf = Cursor::new(b"key\x00value\x00".to_vec());
+        assert_eq!(len_to_null(&mut buf, 10).unwrap(), 3);
+        buf.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(len_to_null(&mut buf, 6).unwrap(), 5);
+        assert_eq!(
+            len_to_null(&mut Cursor::new(b"abc".to_vec()), 3).unwrap(),
+            3
+        );
+        assert_eq!(
+            len_to_null(&mut Cursor::new(b"abc\x00".to_vec()), 2).unwrap(),
+            2
+        );
+    }
+
+    /// intel hex records carry their checksums and segment addresses
+    #[test]
+    fn test_write_intel_hex() {
+        assert_eq!(
+            intel_hex_record(0x0030, 0x00, &[0x02, 0x33, 0x7a]),
+            ":0300300002337A1E"
+        );
+        let mut out = Vec::new();
+        let bytes: Vec<u8> = (0..20).collect();
+        write_intel_hex(&mut Cursor::new(bytes), 0, 20, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ":10000000000102030405060708090A0B0C0D0E0F78\n:0400100010111213A6\n:00000001FF\n"
+        );
+        let mut out = Vec::new();
+        write_intel_hex(&mut Cursor::new(vec![0xaa; 4]), 0xfffe, 4, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let records: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            records,
+            vec![
+                ":02FFFE00AAAAAD",
+                ":020000040001F9",
+                ":02000000AAAAAA",
+                ":00000001FF"
+            ]
+        );
+    }
+
+    /// s-records carry their counts and checksums
+    #[test]
+    fn test_write_srec() {
+        assert_eq!(
+            srec_record(0, 0, 2, b"hello     \x00\x00"),
+            "S00F000068656C6C6F202020202000003C"
+        );
+        assert_eq!(srec_record(5, 3, 2, &[]), "S5030003F9");
+        assert_eq!(srec_record(9, 0, 2, &[]), "S9030000FC");
+        let mut out = Vec::new();
+        write_srec(
+            &mut Cursor::new(vec![0x01, 0x02]),
+            0x1000,
+            2,
+            2,
+            "a",
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "S0040000619A\nS10510000102E7\nS5030001FB\nS9030000FC\n"
+        );
+        let mut out = Vec::new();
+        write_srec(&mut Cursor::new(vec![0xff]), 0, 1, 4, "", &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\nS30600000000FFFA\n"));
+        assert!(out.ends_with("\nS70500000000FA\n"));
+
+        // long headers are truncated to what the count byte can cover
+        let mut out = Vec::new();
+        write_srec(
+            &mut Cursor::new(vec![]),
+            0,
+            0,
+            2,
+            &"x".repeat(300),
+            &mut out,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let header = out.lines().next().unwrap();
+        assert!(header.starts_with("S0FF0000"));
+        assert_eq!(header.len(), 2 + 2 * 0x100);
+    }
+
+    /// checkpoints are reported once per interval reached
+    #[test]
+    fn test_checkpoint() {
+        let mut report = Vec::new();
+        {
+            let mut input = Checkpoint::new(Cursor::new(vec![0u8; 10000]), Some(4096), &mut report);
+            let mut head = [0u8; 100];
+            input.read_exact(&mut head).unwrap();
+            input.seek(SeekFrom::Start(0)).unwrap();
+            io::copy(&mut input, &mut io::sink()).unwrap();
         }
-        if page.bytes == buf_len || max_array_size as u64 == buf_len {
-            page.body.push(line);
-            break;
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "checkpoint: 0x001000\ncheckpoint: 0x002000\n"
+        );
+        let mut report = Vec::new();
+        {
+            let mut input = Checkpoint::new(Cursor::new(vec![0u8; 10000]), Some(4096), &mut report);
+            input.seek(SeekFrom::Start(9000)).unwrap();
+            io::copy(&mut input, &mut io::sink()).unwrap();
+        }
+        assert!(report.is_empty());
+
+        // percentages come from the input length, leaving the checkpoints due
+        let mut report = Vec::new();
+        {
+            let matches = app().get_matches_from(vec!["hex", "--percent", "in.bin"]);
+            let mut input = Checkpoint::new(Cursor::new(vec![0u8; 10000]), Some(4096), &mut report);
+            dump(&matches, &mut input, 10000, "in.bin", &mut io::sink()).unwrap();
         }
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "checkpoint: 0x001000\ncheckpoint: 0x002000\n"
+        );
     }
-    Ok(page)
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    /// @see (https://users.rust-lang.org/t/how-to-test-output-to-stdout/4877/6)
-    /// @see (https://rustbyexample.com/hello/print/print_display.html)
+    /// hex cells carry an escaped multi-format tooltip
     #[test]
-    fn test_offset() {
-        let b: u64 = 0x6;
-        assert_eq!(offset(b), "0x000006");
-        assert_eq!(offset(b), format!("{:#08x}", b));
+    fn test_write_html_tooltips() {
+        assert_eq!(byte_tooltip(0x0a), "dec 10, oct 12, bin 00001010");
+        let page = buf_to_page(&mut Cursor::new(b"<A".to_vec()), 0, 2, 16).unwrap();
+        let mut out = Vec::new();
+        write_html(&page, true, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html
+            .contains("<td title=\"dec 60, oct 74, bin 00111100, char &#39;&lt;&#39;\">3c</td>"));
+        assert!(
+            html.contains("<td title=\"dec 65, oct 101, bin 01000001, char &#39;A&#39;\">41</td>")
+        );
+        assert!(html.contains("<td class=\"ascii\">&lt;A</td>"));
     }
 
-    /// hex octal, takes u8
+    /// the histogram is ordered by value or by descending frequency
     #[test]
-    pub fn test_hex_octal() {
-        let b: u8 = 0x6;
-        assert_eq!(hex_octal(b), "0o0006");
-        assert_eq!(hex_octal(b), format!("{:#06o}", b));
+    fn test_histogram_order() {
+        let counts = histogram(&mut Cursor::new(b"abacabz".to_vec()), 6).unwrap();
+        assert_eq!(
+            histogram_order(&counts, false),
+            vec![(b'a', 3), (b'b', 2), (b'c', 1)]
+        );
+        let counts = histogram(&mut Cursor::new(b"cbcaccb".to_vec()), 7).unwrap();
+        assert_eq!(
+            histogram_order(&counts, true),
+            vec![(b'c', 4), (b'b', 2), (b'a', 1)]
+        );
     }
 
-    /// hex lower hex, takes u8
+    /// listed ranges are carved and concatenated
     #[test]
-    fn test_hex_lower_hex() {
-        let b: u8 = <u8>::max_value(); // 255
-        assert_eq!(hex_lower_hex(b), "0xff");
-        assert_eq!(hex_lower_hex(b), format!("{:#04x}", b));
+    fn test_carve_ranges() {
+        let ranges = parse_ranges_file("# header\n2:4\n\n0x8:\n").unwrap();
+        assert_eq!(ranges, vec![(2, Some(4)), (8, None)]);
+        let mut out = Vec::new();
+        let written = carve_ranges(
+            &mut Cursor::new(b"0123456789".to_vec()),
+            10,
+            &ranges,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(out, b"2389");
+        assert!(parse_ranges_file("4:2").is_err());
     }
 
-    /// hex upper hex, takes u8
+    /// a profile expands to its options, explicit ones taking precedence
     #[test]
-    fn test_hex_upper_hex() {
-        let b: u8 = <u8>::max_value();
-        assert_eq!(hex_upper_hex(b), "0xFF");
-        assert_eq!(hex_upper_hex(b), format!("{:#04X}", b));
+    fn test_expand_profile() {
+        let args: Vec<String> = ["hex", "--profile", "diff", "a.bin"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(
+            expand_profile(&args, |_| false).unwrap(),
+            vec![
+                "hex",
+                "--color",
+                "0",
+                "--no-summary",
+                "--no-trailing-space",
+                "--profile",
+                "diff",
+                "a.bin"
+            ]
+        );
+        let expanded = expand_profile(&args, |arg| arg == "color").unwrap();
+        assert!(!expanded.contains(&"--color".to_string()));
+        assert!(
+            expand_profile(&["hex".to_string(), "--profile=nope".to_string()], |_| {
+                false
+            })
+            .is_err()
+        );
+
+        let page = buf_to_page(&mut Cursor::new(vec![0x41; 20]), 0, 20, 16).unwrap();
+        let mut options = Options::new();
+        options.colorize = parse_color_mode("0", true).unwrap();
+        options.no_summary = true;
+        options.no_trailing_space = true;
+        let mut out = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 2);
+        assert!(!out.contains("bytes:"));
+        assert!(!out.contains('\x1b'));
+        assert!(out.lines().all(|line| !line.ends_with(' ')));
     }
 
-    /// hex binary, takes u8
+    /// der headers decode with long-form lengths and annotate their extent
     #[test]
-    fn test_hex_binary() {
-        let b: u8 = <u8>::max_value();
-        assert_eq!(hex_binary(b), "0b11111111");
-        assert_eq!(hex_binary(b), format!("{:#010b}", b));
+    fn test_decode_der_header() {
+        let header = decode_der_header(&[0x30, 0x82, 0x01, 0x2c, 0x02]).unwrap();
+        assert_eq!(
+            header,
+            DerHeader {
+                class: "universal",
+                constructed: true,
+                tag: 16,
+                length: Some(300),
+                long_form: true,
+                header_len: 4,
+            }
+        );
+        assert_eq!(
+            der_annotation(&header, 0x10),
+            "der universal SEQUENCE, constructed, length 300 (long form), up to 0x000140"
+        );
+        let header = decode_der_header(&[0xbf, 0x87, 0x68, 0x80]).unwrap();
+        assert_eq!(
+            (header.class, header.tag, header.length),
+            ("context", 1000, None)
+        );
+        assert_eq!(decode_der_header(&[0x30, 0x82, 0x01]), None);
+    }
+
+    /// the percentage column tells how far through the input a line starts
+    #[test]
+    fn test_percent_column() {
+        let page = buf_to_page(&mut Cursor::new(vec![0u8; 100]), 0, 100, 10).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 10;
+        options.percent_of = Some(100);
+        let mut out = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("  0% 0x000000: "));
+        assert!(lines[5].starts_with(" 50% 0x000032: "));
+        assert!(lines[9].starts_with(" 90% 0x00005a: "));
+    }
+
+    /// xor extends the shorter input with zeros
+    #[test]
+    fn test_xor_bytes() {
+        assert_eq!(
+            xor_bytes(b"\x0f\xf0\xaa", b"\xff\xff"),
+            vec![0xf0, 0x0f, 0xaa]
+        );
+        assert_eq!(xor_bytes(b"key", b"key"), vec![0, 0, 0]);
+        assert_eq!(xor_bytes(b"", b"\x01"), vec![0x01]);
+    }
+
+    /// the grid frames the dump with rules of the column widths
+    #[test]
+    fn test_print_grid() {
+        let page = buf_to_page(&mut Cursor::new(b"abcdef".to_vec()), 0, 6, 4).unwrap();
+        let mut options = Options::new();
+        options.column_width = 4;
+        let mut out = Vec::new();
+        print_grid(&page, &options, true, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            format!(
+                "\u{250c}{}\u{252c}{}\u{252c}{}\u{2510}",
+                "\u{2500}".repeat(10),
+                "\u{2500}".repeat(21),
+                "\u{2500}".repeat(6)
+            )
+        );
+        assert_eq!(
+            lines[1],
+            "\u{2502} 0x000000 \u{2502} 0x61 0x62 0x63 0x64 \u{2502} abcd \u{2502}"
+        );
+        assert_eq!(
+            lines[2],
+            "\u{2502} 0x000004 \u{2502} 0x65 0x66           \u{2502} ef   \u{2502}"
+        );
+        assert_eq!(
+            lines[3],
+            format!(
+                "\u{2514}{}\u{2534}{}\u{2534}{}\u{2518}",
+                "\u{2500}".repeat(10),
+                "\u{2500}".repeat(21),
+                "\u{2500}".repeat(6)
+            )
+        );
+        assert!(lines[1..3]
+            .iter()
+            .all(|line| line.chars().count() == lines[0].chars().count()));
+
+        let mut out = Vec::new();
+        print_grid(&page, &options, false, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("+----------+---"));
+    }
+
+    /// rust arrays can be emitted as a rustfmt-proof const
+    #[test]
+    fn test_array_rust_const() {
+        let page = buf_to_array(&mut Cursor::new(vec![0x01, 0x02, 0x03]), 3, 10).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_array(&page, "r", None, true, false, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "#[rustfmt::skip]\nconst ARRAY: [u8; 3] = [\n    0x01, 0x02, 0x03\n];\n"
+        );
+    }
+
+    /// a length field in the input sets how many following bytes are dumped
+    #[test]
+    fn test_len_field() {
+        assert_eq!(parse_len_field("0x10:4:le"), Some((16, 4, Endian::Little)));
+        assert_eq!(parse_len_field("0:9:be"), None);
+        assert_eq!(parse_len_field("0:2"), None);
+        let mut input = Cursor::new(vec![0x00, 0x03, 0xaa, 0xbb, 0xcc, 0xdd, 0xee]);
+        let (at, width, endian) = parse_len_field("0:2:be").unwrap();
+        let len = read_len_field(&mut input, at, width, endian).unwrap();
+        assert_eq!(len, 3);
+        let page = buf_to_page(&mut input, 2, len, 16).unwrap();
+        assert_eq!(page.to_bytes(), vec![0xaa, 0xbb, 0xcc]);
+        assert!(read_len_field(&mut input, 6, 2, Endian::Big).is_err());
+    }
+
+    /// only the trailing run of identical lines is collapsed
+    #[test]
+    fn test_trim_trailing() {
+        let mut bytes = vec![0x41; 8];
+        bytes.extend_from_slice(&[0x42; 4]);
+        bytes.extend_from_slice(&[0x00; 16]);
+        let mut page = buf_to_page(&mut Cursor::new(bytes), 0, 28, 4).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 4;
+        options.trimmed_lines = trim_trailing(&mut page);
+        assert_eq!(options.trimmed_lines, 3);
+        assert_eq!(page.body.len(), 4);
+        let mut out = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("0x000000: 0x41"));
+        assert!(lines[1].starts_with("0x000004: 0x41"));
+        assert!(lines[3].starts_with("0x00000c: 0x00"));
+        assert_eq!(lines[4], "* (3 identical lines omitted)");
+        assert_eq!(lines[5], "   bytes: 28");
+    }
+
+    /// swapped groups show their bytes reversed, the gutter unchanged
+    #[test]
+    fn test_swap_words() {
+        let page = buf_to_page(&mut Cursor::new(vec![0, 1, 2, 3, 0x41, 0x42]), 0, 6, 8).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 8;
+        options.swap_words = Some(4);
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("0x000000: 0x03 0x02 0x01 0x00 0x41 0x42 "));
+        assert!(out.trim_end().ends_with("....AB"));
+    }
+
+    /// sorted lines follow their byte order and keep their offsets
+    #[test]
+    fn test_sort_lines() {
+        let mut page = buf_to_page(&mut Cursor::new(b"ccaabbab".to_vec()), 0, 8, 2).unwrap();
+        sort_lines(&mut page);
+        let lines: Vec<(u64, &[u8])> = page
+            .body
+            .iter()
+            .map(|line| (line.offset, &line.hex_body[..]))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (2, &b"aa"[..]),
+                (6, &b"ab"[..]),
+                (4, &b"bb"[..]),
+                (0, &b"cc"[..])
+            ]
+        );
+        assert_eq!(page.bytes, 8);
+
+        // runs are found in file order before the lines are sorted
+        let mut data = b"zyxwvutsrqponmlk".to_vec();
+        data.extend_from_slice(&[0x00; 16]);
+        let out = dump_args(&["-t", "0", "--cols", "16", "--sort-lines", "--rle"], data).unwrap();
+        assert!(
+            out.contains(&format!("{}: 0x00\u{d7}16", offset(0x10))),
+            "{}",
+            out
+        );
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[1].starts_with(&format!("{}: 0x00 ", offset(0x10))));
+        assert!(lines[2].starts_with(&format!("{}: 0x7a ", offset(0))));
+    }
+
+    /// identical consecutive lines show `=`, changed ones a direction
+    #[test]
+    fn test_crc_deltas() {
+        let page = buf_to_page(&mut Cursor::new(b"aaaabbbb".to_vec()), 0, 8, 2).unwrap();
+        let deltas: Vec<char> = crc_deltas(&page).iter().map(|(_, delta)| *delta).collect();
+        assert_eq!(deltas[0], '-');
+        assert_eq!(deltas[1], '=');
+        assert!(deltas[2] == '\u{2191}' || deltas[2] == '\u{2193}');
+        assert_eq!(deltas[3], '=');
+
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 2;
+        options.crc_deltas = crc_deltas(&page);
+        let mut out = Vec::new();
+        print_line(&page.body[1], 1, &options, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("0x000002: 0x61 0x61 aa crc {:08x} =\n", crc32(b"aa"))
+        );
+    }
+
+    /// command output is captured, failing commands are errors
+    #[test]
+    #[cfg(unix)]
+    fn test_command_output() {
+        assert_eq!(command_output("printf abc", None).unwrap(), b"abc");
+        match command_output("printf partial; exit 3", None) {
+            Err(Error::Application(message)) => assert!(message.contains("printf partial")),
+            _ => panic!("a failing command is an error"),
+        }
+        let timeout = Some(Duration::from_millis(50));
+        assert_eq!(command_output("printf abc", timeout).unwrap(), b"abc");
+        match command_output("sleep 5", timeout) {
+            Err(Error::Application(message)) => assert!(message.contains("timed out")),
+            _ => panic!("a stalled command times out"),
+        }
+    }
+
+    /// upper case applies to dump bytes, array elements and plain octets
+    #[test]
+    fn test_upper_case() {
+        let page = buf_to_page(&mut Cursor::new(vec![0xab, 0x0f]), 0, 2, 16).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.format = Format::UpperHex;
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("0x000000: 0xAB 0x0F "));
+        let mut out = Vec::new();
+        print_array(&page, "c", None, false, true, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("0xAB, 0x0F"));
+        let mut out = Vec::new();
+        print_plain(&page, " ", true, &mut out).unwrap();
+        assert_eq!(out, b"AB 0F\n");
+    }
+
+    /// the decoded column shows the gutter text transformed
+    #[test]
+    fn test_decode_column() {
+        assert_eq!(
+            decode_gutter("Uryyb, jbeyq!", Decoding::Rot13),
+            "Hello, world!"
+        );
+        assert_eq!(decode_gutter("aGV5IQ==", Decoding::Base64), "hey!");
+        assert_eq!(decode_gutter("aGk=a.bc", Decoding::Base64), "hi?");
+        assert_eq!(decode_gutter("a%20b+c%0a%", Decoding::Url), "a b c.%");
+
+        let page = buf_to_page(&mut Cursor::new(b"uryyb".to_vec()), 0, 5, 8).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 8;
+        options.decode_column = Some(Decoding::Rot13);
+        let mut out = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with(" uryyb    hello\n"));
+    }
+
+    /// dumps buffering more than the memory limit fail before allocating
+    #[test]
+    fn test_check_memory() {
+        let needed = page_memory(1 << 30, 16);
+        assert!(needed > 1 << 30);
+        match check_memory(needed, Some(1 << 20)) {
+            Err(Error::Application(message)) => assert!(message.contains("--max-memory")),
+            _ => panic!("a page over the limit is an error"),
+        }
+        assert!(check_memory(page_memory(100, 16), Some(1 << 20)).is_ok());
+        assert!(check_memory(needed, None).is_ok());
+    }
+
+    /// every buffering mode honours the memory limit
+    #[test]
+    fn test_max_memory_modes() {
+        let baseline_path =
+            std::env::temp_dir().join(format!("hex-max-memory-{}", std::process::id()));
+        fs::write(&baseline_path, vec![0u8; 4096]).unwrap();
+        let baseline = baseline_path.to_string_lossy().to_string();
+        let data = vec![0u8; 4096];
+        for args in [
+            &["--html"][..],
+            &["--xor-with", &baseline][..],
+            &["--interleave", &baseline][..],
+            &["--period"][..],
+            &["--baseline", &baseline, "--len", "16"][..],
+        ] {
+            let mut limited = args.to_vec();
+            limited.extend_from_slice(&["--max-memory", "1024"]);
+            match dump_args(&limited, data.clone()) {
+                Err(Error::Application(message)) => assert!(message.contains("--max-memory")),
+                _ => panic!("{:?} buffers past the limit", args),
+            }
+        }
+        // a resumable dump only buffers a line at a time
+        let state_path =
+            std::env::temp_dir().join(format!("hex-max-memory-state-{}", std::process::id()));
+        let state = state_path.to_string_lossy().to_string();
+        assert!(dump_args(&["--state", &state, "--max-memory", "1024"], data).is_ok());
+        let _ = fs::remove_file(&state_path);
+        let _ = fs::remove_file(&baseline_path);
+    }
+
+    /// saved options replay to the same command line options
+    #[test]
+    fn test_replay_options() {
+        let matches = app().get_matches_from(vec![
+            "hex",
+            "--cols",
+            "8",
+            "-t0",
+            "--zebra",
+            "--save-options",
+            "recipe",
+            "--prefix=-a=b",
+            "-vv",
+            "--uuid-at",
+            "0",
+            "--uuid-at=16",
+            "in.bin",
+        ]);
+        let saved = save_options(&matches);
+        assert_eq!(
+            saved,
+            "cols=8\ncolor=0\nzebra\nprefix=-a=b\n-v\n-v\nuuid-at=0\nuuid-at=16\n"
+        );
+
+        let path = std::env::temp_dir().join(format!("hex-replay-{}", std::process::id()));
+        fs::write(&path, &saved).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let replay: Vec<String> = ["hex", "--replay", &path, "in.bin"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let replayed = replay_options(&replay, |_| false).unwrap();
+        assert_eq!(save_options(&app().get_matches_from(&replayed)), saved);
+        let replayed = replay_options(&replay, |name| name == "cols" || name == "v").unwrap();
+        assert!(!replayed.contains(&"--cols=8".to_string()));
+        assert!(!replayed.contains(&"-v".to_string()));
+        assert!(replayed.contains(&"--zebra".to_string()));
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// every long option of the help page is a known argument name
+    #[test]
+    fn test_argument_names() {
+        let mut help = Vec::new();
+        app().write_long_help(&mut help).unwrap();
+        let help = String::from_utf8(help).unwrap();
+        let options: Vec<&str> = help
+            .lines()
+            .map(str::trim_start)
+            .filter(|line| line.starts_with('-'))
+            .filter_map(|line| line.split_whitespace().find(|word| word.starts_with("--")))
+            .map(|option| option.trim_start_matches('-'))
+            .filter(|option| !["help", "version"].contains(option))
+            .collect();
+        assert!(options.len() > 100);
+        for option in options {
+            assert!(ARGUMENT_NAMES.contains(&option), "--{} is unknown", option);
+        }
+    }
+
+    /// a replayed run gives the output of the run that saved its options
+    #[test]
+    fn test_save_and_replay() {
+        let path = |name: &str| {
+            let path = std::env::temp_dir().join(format!("hex-{}-{}", name, std::process::id()));
+            path.to_string_lossy().to_string()
+        };
+        let (input, options) = (path("replay-in"), path("replay-options"));
+        let (saved, replayed, overridden) = (
+            path("saved-out"),
+            path("replayed-out"),
+            path("overridden-out"),
+        );
+        fs::write(&input, (0..64u8).collect::<Vec<u8>>()).unwrap();
+        let run_line = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            run_args(&args)
+        };
+
+        run_line(&[
+            "hex",
+            "--save-options",
+            &options,
+            "--cols",
+            "8",
+            "--prefix=-> ",
+            "-vv",
+            "--uuid-at",
+            "0",
+            "--uuid-at",
+            "16",
+            "-t0",
+            "--output",
+            &saved,
+            &input,
+        ])
+        .unwrap();
+        run_line(&["hex", "--replay", &options, "--output", &replayed, &input]).unwrap();
+        let saved_out = fs::read_to_string(&saved).unwrap();
+        assert!(saved_out.starts_with("-> verbose 2"));
+        assert_eq!(saved_out, fs::read_to_string(&replayed).unwrap());
+
+        // explicit options, repeatable ones too, take precedence
+        run_line(&[
+            "hex",
+            "--replay",
+            &options,
+            "-v",
+            "--output",
+            &overridden,
+            &input,
+        ])
+        .unwrap();
+        assert!(fs::read_to_string(&overridden)
+            .unwrap()
+            .starts_with("-> verbose 1"));
+        for path in [input, options, saved, replayed, overridden] {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// each block gets its own checksum line at its offset
+    #[test]
+    fn test_block_hashes() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+
+        let mut data = b"123456789".to_vec();
+        data.extend_from_slice(b"abcdefghi");
+        let mut buf = Cursor::new(data);
+        let hashes = block_hashes(&mut buf, 0x40, 18, 9).unwrap();
+        assert_eq!(
+            hashes,
+            vec![(0x40, 0xcbf4_3926), (0x49, crc32(b"abcdefghi"))]
+        );
+        let mut buf = Cursor::new(b"123456789".to_vec());
+        assert_eq!(block_hashes(&mut buf, 0, 9, 4).unwrap().len(), 3);
+    }
+
+    /// the first non-printable byte is reported at its offset
+    #[test]
+    fn test_first_nonprintable() {
+        let text = b"key = value\n\tother\r\n".to_vec();
+        let len = text.len() as u64;
+        assert_eq!(
+            first_nonprintable(&mut Cursor::new(text), 0x20, len).unwrap(),
+            None
+        );
+        let binary = b"abc\x01def\x00".to_vec();
+        assert_eq!(
+            first_nonprintable(&mut Cursor::new(binary), 0x20, 8).unwrap(),
+            Some((0x23, 0x01))
+        );
+    }
+
+    /// zero runs are reported with their offsets, short ones filtered
+    #[test]
+    fn test_null_runs() {
+        let data = b"ab\x00\x00\x00cd\x00ef\x00\x00".to_vec();
+        let len = data.len() as u64;
+        let mut buf = Cursor::new(data.clone());
+        assert_eq!(
+            null_runs(&mut buf, 0x10, len, 1).unwrap(),
+            vec![(0x12, 3), (0x17, 1), (0x1a, 2)]
+        );
+        let mut buf = Cursor::new(data);
+        assert_eq!(
+            null_runs(&mut buf, 0x10, len, 2).unwrap(),
+            vec![(0x12, 3), (0x1a, 2)]
+        );
+    }
+
+    /// code pages map high bytes to their glyphs, controls stay dots
+    #[test]
+    fn test_codepage() {
+        assert_eq!(codepage_char(0xdb, Codepage::Cp437), '█');
+        assert_eq!(codepage_char(0xc9, Codepage::Cp437), '╔');
+        assert_eq!(codepage_char(0x80, Codepage::Cp437), 'Ç');
+        assert_eq!(codepage_char(0xe1, Codepage::Cp437), 'ß');
+        assert_eq!(codepage_char(0xfe, Codepage::Cp437), '■');
+        assert_eq!(codepage_char(0xe9, Codepage::Latin1), 'é');
+        assert_eq!(codepage_char(0x80, Codepage::Latin1), '.');
+        assert_eq!(codepage_char(0x80, Codepage::Cp1252), '€');
+        assert_eq!(codepage_char(0x81, Codepage::Cp1252), '.');
+        assert_eq!(codepage_gutter(b"A\x00\xb0", Codepage::Cp437), "A.░");
+        assert_eq!(CP437_HIGH.chars().count(), 128);
+        assert_eq!(CP1252_C1.chars().count(), 32);
+    }
+
+    /// a reader that never yields data times out instead of hanging
+    #[test]
+    fn test_read_with_timeout() {
+        struct Stalled;
+        impl Read for Stalled {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                thread::sleep(Duration::from_secs(3600));
+                Ok(0)
+            }
+        }
+
+        let started = Instant::now();
+        match read_with_timeout(Stalled, Duration::from_millis(50)) {
+            Err(Error::Application(message)) => assert!(message.contains("timed out")),
+            _ => panic!("stalled read did not time out"),
+        }
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let bytes =
+            read_with_timeout(Cursor::new(vec![7u8; 10000]), Duration::from_secs(5)).unwrap();
+        assert_eq!(bytes, vec![7u8; 10000]);
+    }
+
+    /// modifying the watched file clears the screen and dumps it again
+    #[test]
+    fn test_watch() {
+        let path = std::env::temp_dir().join(format!("hex-watch-{}", std::process::id()));
+        fs::write(&path, b"abcd").unwrap();
+
+        let writer_path = path.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            // replaced at once, the watcher never sees a partial write
+            let staged = writer_path.with_extension("staged");
+            fs::write(&staged, b"wxyz!").unwrap();
+            fs::rename(&staged, &writer_path).unwrap();
+        });
+
+        let mut options = Options::new();
+        options.colorize = false;
+        let mut out: Vec<u8> = Vec::new();
+        let deadline = Instant::now() + Duration::from_millis(500);
+        watch(
+            &path,
+            Duration::from_millis(10),
+            Some(deadline),
+            &mut out,
+            || None,
+            |w| {
+                let bytes = fs::read(&path)?;
+                let page = buf_to_array(&mut Cursor::new(bytes), 16, 10)?;
+                print_page(&page, &options, w)
+            },
+        )
+        .unwrap();
+        writer.join().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let out = String::from_utf8(out).unwrap();
+        let dumps: Vec<&str> = out.split(CLEAR_SCREEN).collect();
+        assert_eq!(dumps.len(), 2);
+        assert!(dumps[0].contains("abcd"));
+        assert!(dumps[1].contains("wxyz!"));
+    }
+
+    /// a terminal resize redraws the watched file at the new column count
+    #[test]
+    fn test_watch_resize() {
+        let path = std::env::temp_dir().join(format!("hex-resize-{}", std::process::id()));
+        fs::write(&path, (0..32).collect::<Vec<u8>>()).unwrap();
+
+        let mut options = Options::new();
+        options.colorize = false;
+        assert_eq!(fit_columns(80, &options), 11);
+        assert_eq!(fit_columns(40, &options), 5);
+        assert_eq!(fit_columns(5, &options), 1);
+
+        let started = Instant::now();
+        let width = || {
+            if started.elapsed() < Duration::from_millis(100) {
+                Some(80)
+            } else {
+                Some(40)
+            }
+        };
+        let mut out: Vec<u8> = Vec::new();
+        watch(
+            &path,
+            Duration::from_millis(10),
+            Some(started + Duration::from_millis(300)),
+            &mut out,
+            width,
+            |w| {
+                let mut options = options.clone();
+                options.column_width = fit_columns(width().unwrap(), &options);
+                let bytes = fs::read(&path)?;
+                let page = buf_to_array(&mut Cursor::new(bytes), 32, options.column_width)?;
+                print_page(&page, &options, w)
+            },
+        )
+        .unwrap();
+        let _ = fs::remove_file(&path);
+
+        let out = String::from_utf8(out).unwrap();
+        let dumps: Vec<&str> = out.split(CLEAR_SCREEN).collect();
+        assert_eq!(dumps.len(), 2);
+        let columns = |dump: &str| dump.lines().next().unwrap().matches("0x").count() - 1;
+        assert_eq!(columns(dumps[0]), 11);
+        assert_eq!(columns(dumps[1]), 5);
+    }
+
+    /// the dump written to a connected socket reaches the listener unchanged
+    #[test]
+    fn test_connect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let receiver = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received: Vec<u8> = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut options = Options::new();
+        options.colorize = false;
+        let mut buf = Cursor::new(b"remote".to_vec());
+        let page = buf_to_array(&mut buf, 6, 10).unwrap();
+        let mut expected: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut expected).unwrap();
+
+        let mut stream = connect(&addr).unwrap();
+        print_page(&page, &options, &mut stream).unwrap();
+        drop(stream);
+        assert_eq!(receiver.join().unwrap(), expected);
+
+        assert!(connect("127.0.0.1:0").is_err());
+    }
+
+    /// follow dumps the existing bytes, then the appended ones at continuing offsets
+    #[test]
+    fn test_follow() {
+        let path = std::env::temp_dir().join(format!("hex-follow-{}", std::process::id()));
+        fs::write(&path, b"abcd").unwrap();
+
+        let writer_path = path.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(&writer_path)
+                .unwrap();
+            file.write_all(b"efgh").unwrap();
+        });
+
+        let mut options = Options::new();
+        options.colorize = false;
+        let mut out: Vec<u8> = Vec::new();
+        let deadline = Instant::now() + Duration::from_millis(500);
+        follow(
+            &path,
+            0,
+            &options,
+            Duration::from_millis(10),
+            Some(deadline),
+            &mut out,
+        )
+        .unwrap();
+        writer.join().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&format!("{}: ", offset(0))));
+        assert!(lines[0].ends_with("abcd"));
+        assert!(lines[1].starts_with(&format!("{}: ", offset(4))));
+        assert!(lines[1].ends_with("efgh"));
+    }
+
+    /// only whole files can be followed
+    #[test]
+    fn test_follow_inputs() {
+        for args in [
+            &["hex", "--follow", "--len", "4", "in.bin"][..],
+            &["hex", "--follow", "--range", "0:4", "in.bin"][..],
+            &["hex", "--follow", "--cmd", "true"][..],
+        ] {
+            let e = app().get_matches_from_safe(args).unwrap_err();
+            assert_eq!(e.kind, clap::ErrorKind::ArgumentConflict);
+        }
+        let matches = app().get_matches_from(vec!["hex", "--follow", "-"]);
+        let mut out = Vec::new();
+        match dump(
+            &matches,
+            &mut Cursor::new(vec![0u8; 4]),
+            4,
+            "stdin",
+            &mut out,
+        ) {
+            Err(Error::Application(message)) => assert!(message.contains("stdin")),
+            _ => panic!("stdin cannot be followed"),
+        }
+    }
+
+    /// without trailing space no line ends in whitespace, the layout is kept
+    #[test]
+    fn test_no_trailing_space() {
+        let mut options = Options::new();
+        options.colorize = false;
+        let data = b"0123456789abc  ".to_vec();
+        let page = buf_to_array(&mut Cursor::new(data), 15, 10).unwrap();
+        let mut padded: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut padded).unwrap();
+
+        options.no_trailing_space = true;
+        let mut trimmed: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut trimmed).unwrap();
+        let padded = String::from_utf8(padded).unwrap();
+        let trimmed = String::from_utf8(trimmed).unwrap();
+        for line in trimmed.lines() {
+            assert_eq!(line, line.trim_end());
+        }
+        let padded: Vec<&str> = padded.lines().collect();
+        let trimmed: Vec<&str> = trimmed.lines().collect();
+        assert_eq!(padded[0], trimmed[0]);
+        assert_eq!(padded[1].trim_end(), trimmed[1]);
+
+        let page = buf_to_array(&mut Cursor::new(b"  ".to_vec()), 2, 10).unwrap();
+        let mut blank: Vec<u8> = Vec::new();
+        print_line(&page.body[0], 0, &options, &mut blank).unwrap();
+        assert_eq!(String::from_utf8(blank).unwrap(), "0x000000: 0x20 0x20\n");
+    }
+
+    /// line numbers prefix the offsets only when enabled
+    #[test]
+    fn test_line_numbers() {
+        let mut buf = Cursor::new(vec![0x00u8; 20]);
+        let page = buf_to_array(&mut buf, 20, 16).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 16;
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("0x000000: "));
+
+        options.line_numbers = true;
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("1: 0x000000: "));
+        assert!(lines[1].starts_with("2: 0x000010: "));
+    }
+
+    /// compact lines are shorter and stay aligned
+    #[test]
+    fn test_compact() {
+        let mut buf = Cursor::new(b"abcdefghij".to_vec());
+        let page = buf_to_array(&mut buf, 10, 8).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 8;
+        let mut default: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut default).unwrap();
+        options.compact = true;
+        let mut compact: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut compact).unwrap();
+        let default = String::from_utf8(default).unwrap();
+        let compact = String::from_utf8(compact).unwrap();
+        let default: Vec<&str> = default.lines().collect();
+        let compact: Vec<&str> = compact.lines().collect();
+
+        assert_eq!(compact[0], "000000 61 62 63 64 65 66 67 68 abcdefgh");
+        assert!(compact[0].len() < default[0].len());
+        assert_eq!(compact[1].find("ij"), compact[0].find("abcdefgh"));
+        assert_eq!(default[1].find("ij"), default[0].find("abcdefgh"));
+    }
+
+    /// tlv records are split and annotated, overruns flagged
+    #[test]
+    fn test_tlv() {
+        let data = vec![0x01u8, 0x02, 0xaa, 0xbb, 0x02, 0x01, 0xcc, 0x03, 0x05, 0xdd];
+        let records = parse_tlv(&data, 0, 1, 1, Endian::Big);
+        assert_eq!(records.len(), 3);
+        assert_eq!((records[0].offset, records[0].length), (0, Some(2)));
+        assert_eq!((records[1].offset, records[1].length), (4, Some(1)));
+        assert_eq!((records[2].offset, records[2].overrun), (7, 4));
+        assert_eq!(tlv_note(&records[0]), "tlv type 0x1 length 2");
+        assert_eq!(
+            tlv_note(&records[2]),
+            "tlv type 0x3 length 5 overruns input by 4 bytes"
+        );
+        assert_eq!(
+            parse_tlv(&[0x01, 0x02, 0x00], 0, 1, 2, Endian::Little)[0].length,
+            Some(2)
+        );
+        assert_eq!(parse_tlv(&[0x01], 0, 1, 1, Endian::Big)[0].tag, None);
+        assert_eq!(parse_tlv_widths("2:4"), Some((2, 4)));
+        assert_eq!(parse_tlv_widths("2:9"), None);
+
+        let mut buf = Cursor::new(data.clone());
+        let page = buf_to_array(&mut buf, 10, 16).unwrap();
+        let boundaries: Vec<u64> = records.iter().map(|tlv| tlv.offset).collect();
+        let page = split_at(page, &boundaries, 16);
+        let mut options = Options::new();
+        options.colorize = false;
+        for tlv in records.iter() {
+            options
+                .annotations
+                .entry(tlv.offset)
+                .or_default()
+                .push(tlv_note(tlv));
+        }
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "; 0x000000: tlv type 0x1 length 2");
+        assert!(lines[1].starts_with("0x000000: 0x01 0x02 0xaa 0xbb "));
+        assert_eq!(lines[2], "; 0x000004: tlv type 0x2 length 1");
+        assert!(lines[3].starts_with("0x000004: 0x02 0x01 0xcc "));
+        assert_eq!(
+            lines[4],
+            "; 0x000007: tlv type 0x3 length 5 overruns input by 4 bytes"
+        );
+        assert!(lines[5].starts_with("0x000007: 0x03 0x05 0xdd "));
+    }
+
+    /// struct fields are decoded and annotated
+    #[test]
+    fn test_struct() {
+        let fields = parse_layout("u32:magic,u16:version,i8:delta,3s:name").unwrap();
+        assert_eq!(fields[0].kind, FieldKind::Unsigned(4));
+        assert_eq!(fields[3].kind, FieldKind::Str(3));
+        assert_eq!(parse_layout("u24:x"), None);
+        assert_eq!(parse_layout("u32"), None);
+
+        let data = vec![0x7f, 0x45, 0x4c, 0x46, 0x02, 0x00, 0xfe, 0x65, 0x6c];
+        let decoded = decode_struct(&data, 0, &fields, Endian::Little);
+        assert_eq!(
+            decoded,
+            vec![
+                (0, "magic: u32 = 0x464c457f (1179403647)".to_string()),
+                (4, "version: u16 = 0x2 (2)".to_string()),
+                (6, "delta: i8 = -2".to_string()),
+                (7, "name: 3s extends past end of input".to_string()),
+            ]
+        );
+        let decoded = decode_struct(&data, 0, &fields[..1], Endian::Big);
+        assert_eq!(decoded[0].1, "magic: u32 = 0x7f454c46 (2135247942)");
+    }
+
+    /// lines labelled with the offset of their last byte
+    #[test]
+    fn test_offset_last() {
+        let mut buf = Cursor::new(vec![0x00u8; 20]);
+        let page = buf_to_array(&mut buf, 20, 16).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 16;
+        options.offset_last = true;
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("0x00000f: "));
+        assert!(lines[1].starts_with("0x000013: "));
+    }
+
+    /// separators are drawn at instruction boundaries
+    #[test]
+    fn test_insn_lengths() {
+        let lengths = parse_lengths("1,3,2").unwrap();
+        assert_eq!(insn_boundaries(0, &lengths), vec![1, 4, 6]);
+        assert_eq!(parse_lengths("1\n3 2"), Some(vec![1, 3, 2]));
+        assert_eq!(parse_lengths("1,x"), None);
+
+        let mut buf = Cursor::new(vec![0x90u8, 0x48, 0x89, 0xe5, 0xc3, 0x00]);
+        let page = buf_to_array(&mut buf, 6, 16).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.insn_boundaries = insn_boundaries(0, &lengths);
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("0x000000: 0x90|0x48 0x89 0xe5|0xc3 0x00|"));
+    }
+
+    /// the dump starts at the requested percentage of the input
+    #[test]
+    fn test_seek_percent() {
+        assert_eq!(seek_percent_offset(Some(200), 50.0).unwrap(), 100);
+        assert_eq!(seek_percent_offset(Some(200), 100.0).unwrap(), 200);
+        assert!(seek_percent_offset(None, 50.0).is_err());
+        assert!(seek_percent_offset(Some(200), 150.0).is_err());
+
+        let data: Vec<u8> = (0..200u8).collect();
+        let mut buf = Cursor::new(data);
+        let skip = seek_percent_offset(Some(200), 50.0).unwrap();
+        buf.seek(SeekFrom::Start(skip)).unwrap();
+        let page = buf_to_page(&mut buf, skip, 200 - skip, 16).unwrap();
+        assert_eq!(page.body[0].offset, 100);
+        assert_eq!(page.body[0].hex_body[0], 100);
+        assert_eq!(page.bytes, 100);
+    }
+
+    /// gutter delimiters wrap the ascii gutter
+    #[test]
+    fn test_gutter_delim() {
+        assert_eq!(parse_gutter_delim("none"), Some(None));
+        assert_eq!(parse_gutter_delim("<>"), Some(Some(('<', '>'))));
+        assert_eq!(parse_gutter_delim("|"), None);
+
+        let mut buf = Cursor::new(b"il\n".to_vec());
+        let page = buf_to_array(&mut buf, 3, 4).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 4;
+        options.gutter_delim = parse_gutter_delim("||").unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out.lines().next().unwrap(),
+            "0x000000: 0x69 0x6c 0x0a      |il.|"
+        );
+    }
+
+    /// delimiters inside the gutter are escaped
+    #[test]
+    fn test_escape_gutter() {
+        let mut buf = Cursor::new(b"a|b\\".to_vec());
+        let page = buf_to_array(&mut buf, 4, 4).unwrap();
+        let mut options = Options::new();
+        options.colorize = false;
+        options.column_width = 4;
+        options.gutter_delim = parse_gutter_delim("||").unwrap();
+        options.escape_gutter = true;
+        let mut out: Vec<u8> = Vec::new();
+        print_page(&page, &options, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out.lines().next().unwrap(),
+            "0x000000: 0x61 0x7c 0x62 0x5c |a\\|b\\\\|"
+        );
+    }
+
+    /// every json line is a standalone json object
+    #[test]
+    fn test_print_jsonl_line() {
+        let mut buf = Cursor::new(b"ab\"\\\x00cd".to_vec());
+        let page = buf_to_array(&mut buf, 7, 4).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        for line in page.body.iter() {
+            print_jsonl_line(line, &mut out).unwrap();
+        }
+        let out = String::from_utf8(out).unwrap();
+        let values: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0]["offset"], 0);
+        assert_eq!(values[0]["bytes"], serde_json::json!([97, 98, 34, 92]));
+        assert_eq!(values[0]["ascii"], "ab\"\\");
+        assert_eq!(values[1]["offset"], 4);
+        assert_eq!(values[1]["ascii"], ".cd");
+    }
+
+    /// ranges and fill checks
+    #[test]
+    fn test_check_fill() {
+        assert_eq!(parse_range("0x10:0x20"), Some((0x10, Some(0x20))));
+        assert_eq!(parse_range("4:"), Some((4, None)));
+        assert_eq!(parse_range("8:4"), None);
+
+        let mut data: Vec<u8> = vec![0x00; 4];
+        data.extend(vec![0xff; 8]);
+        let mut buf = Cursor::new(data.clone());
+        buf.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(check_fill(&mut buf, 4, 8, 0xff).unwrap(), None);
+
+        data[9] = 0x7f;
+        let mut buf = Cursor::new(data);
+        buf.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(check_fill(&mut buf, 4, 8, 0xff).unwrap(), Some((9, 0x7f)));
+    }
+
+    /// writer failing after a number of writes, simulates an interruption
+    struct Interrupted {
+        out: Vec<u8>,
+        writes: usize,
+    }
+
+    impl Write for Interrupted {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.writes == 0 {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "interrupted"));
+            }
+            self.writes -= 1;
+            self.out.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// complete dump lines, any partially written line is dropped
+    fn dumped_offsets(out: &[u8]) -> Vec<String> {
+        let out = String::from_utf8_lossy(out);
+        let complete = out.rfind('\n').map_or("", |end| &out[..end]);
+        complete
+            .split('\n')
+            .filter(|line| line.starts_with("0x"))
+            .map(|line| line[..8].to_string())
+            .collect()
+    }
+
+    /// Output of `dump` for a command line, the input file being `input`.
+    fn dump_args(args: &[&str], input: Vec<u8>) -> Result<String> {
+        let mut command_line = vec!["hex"];
+        command_line.extend_from_slice(args);
+        command_line.push("in.bin");
+        let matches = app().get_matches_from_safe(command_line).unwrap();
+        let len = input.len() as u64;
+        let mut out = Vec::new();
+        dump(&matches, &mut Cursor::new(input), len, "in.bin", &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// a resumable dump lays out the same lines as a regular one
+    #[test]
+    fn test_dump_resumable_range() {
+        let state_path =
+            std::env::temp_dir().join(format!("hex-state-skip-{}", std::process::id()));
+        let state = state_path.to_string_lossy().to_string();
+        let data: Vec<u8> = (0..64u8).collect();
+        let _ = fs::remove_file(&state_path);
+
+        let out = dump_args(
+            &["-t", "0", "--state", &state, "--skip", "32"],
+            data.clone(),
+        )
+        .unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("0x000020: 0x20 0x21 "));
+        assert!(lines[1].starts_with("0x00002a: 0x2a "));
+        assert_eq!(lines.last(), Some(&"   bytes: 32"));
+
+        for args in [
+            &["-t", "0", "--skip", "32", "--repeat", "2"][..],
+            &["-t", "0", "--range", "8:40", "--record-size", "12"][..],
+        ] {
+            let _ = fs::remove_file(&state_path);
+            let mut with_state = args.to_vec();
+            with_state.extend_from_slice(&["--state", &state]);
+            assert_eq!(
+                dump_args(&with_state, data.clone()).unwrap(),
+                dump_args(args, data.clone()).unwrap()
+            );
+        }
+
+        // resuming continues after the saved progress past the start offset
+        State {
+            offset: 20,
+            hash: fnv1a(&data),
+        }
+        .save(&state_path)
+        .unwrap();
+        let out = dump_args(
+            &["-t", "0", "--state", &state, "--resume", "--skip", "32"],
+            data.clone(),
+        )
+        .unwrap();
+        assert!(out.starts_with("0x000034: 0x34 "));
+        assert!(out.ends_with("   bytes: 12\n"));
+        let _ = fs::remove_file(&state_path);
+    }
+
+    /// options a resumable dump cannot honour are rejected with --state
+    #[test]
+    fn test_state_conflicts() {
+        for option in [
+            &["--array", "c"][..],
+            &["--plain"][..],
+            &["--oneline"][..],
+            &["--jsonl"][..],
+            &["--until-ascii", "abc"][..],
+            &["--mark", "61"][..],
+            &["--sort-lines"][..],
+            &["--crc-delta"][..],
+            &["--trim-trailing"][..],
+            &["--rle"][..],
+            &["--align", "16"][..],
+            &["--split-on", "0"][..],
+            &["--tlv"][..],
+            &["--struct", "u8:a"][..],
+            &["--markdown"][..],
+            &["--diff", "other.bin"][..],
+            &["--grid"][..],
+            &["--print-page", "10"][..],
+            &["--class-summary"][..],
+        ] {
+            let mut args = vec!["hex", "--state", "st"];
+            args.extend_from_slice(option);
+            args.push("in.bin");
+            let e = app().get_matches_from_safe(args).unwrap_err();
+            assert_eq!(e.kind, clap::ErrorKind::ArgumentConflict, "{:?}", option);
+        }
+    }
+
+    /// an interrupted then resumed dump covers all bytes exactly once
+    #[test]
+    fn test_dump_resumable() {
+        let state_path = std::env::temp_dir().join(format!("hex-state-{}", std::process::id()));
+        let _ = fs::remove_file(&state_path);
+        let data: Vec<u8> = (0..100u8).collect();
+        let mut options = Options::new();
+        options.colorize = false;
+
+        let mut first = Interrupted {
+            out: Vec::new(),
+            writes: 50,
+        };
+        let mut buf = Cursor::new(data.clone());
+        let progress = Progress {
+            path: &state_path,
+            resume: true,
+            save_every: 1,
+        };
+        assert!(dump_resumable(&mut buf, 0, 100, 1, &options, progress, &mut first).is_err());
+
+        let mut second: Vec<u8> = Vec::new();
+        let mut buf = Cursor::new(data.clone());
+        dump_resumable(&mut buf, 0, 100, 1, &options, progress, &mut second).unwrap();
+
+        let mut offsets = dumped_offsets(&first.out);
+        assert!(!offsets.is_empty());
+        offsets.extend(dumped_offsets(&second));
+        let expected: Vec<String> = (0..10).map(|i| offset(i * 10)).collect();
+        assert_eq!(offsets, expected);
+
+        // a changed file invalidates the state
+        let mut changed = data.clone();
+        changed[0] = 0xff;
+        let mut third: Vec<u8> = Vec::new();
+        let mut buf = Cursor::new(changed);
+        dump_resumable(&mut buf, 0, 100, 1, &options, progress, &mut third).unwrap();
+        assert_eq!(dumped_offsets(&third), expected);
+        let _ = fs::remove_file(&state_path);
     }
 }